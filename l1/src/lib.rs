@@ -0,0 +1,290 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use alloy_primitives::B256;
+use log::{info, warn};
+use rollup::{Address, Block, Blockchain, MerkleProof, StateRoots};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, Mutex};
+
+/// The state and withdrawal roots of a batch of L2 blocks, committed to the L1.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct BatchCommitment {
+    /// The index of the batch.
+    pub index: u64,
+    /// The roots of the L2 state after applying the batch.
+    pub roots: StateRoots,
+}
+
+/// A deposit of funds from the L1 to a recipient on the L2.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Deposit {
+    /// The index of the deposit, incremented with every deposit.
+    pub index: u64,
+    /// The address of the recipient on the L2.
+    pub recipient: Address,
+    /// The amount of value deposited.
+    pub amount: u64,
+}
+
+/// An error encountered while settling on the [L1].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum L1Error {
+    /// The batch index does not follow the last committed batch.
+    UnexpectedBatch { expected: u64, actual: u64 },
+    /// No batch has been committed at the given index.
+    UnknownBatch(u64),
+    /// The withdrawal proof does not verify against the batch's withdrawals root.
+    InvalidProof,
+    /// The withdrawal has already exited.
+    AlreadyFinalized,
+}
+
+impl std::fmt::Display for L1Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            L1Error::UnexpectedBatch { expected, actual } => {
+                write!(f, "unexpected batch index {actual}, expected {expected}")
+            }
+            L1Error::UnknownBatch(index) => write!(f, "unknown batch {index}"),
+            L1Error::InvalidProof => write!(f, "invalid withdrawal proof"),
+            L1Error::AlreadyFinalized => write!(f, "withdrawal already finalized"),
+        }
+    }
+}
+
+impl std::error::Error for L1Error {}
+
+/// A mock of the rollup's L1 settlement contract, run in-process.
+/// Stores batch commitments, finalizes withdrawal exits and emits deposit events.
+pub struct L1 {
+    /// The committed batches, by batch index.
+    batches: Vec<BatchCommitment>,
+    /// The hashes of the withdrawals that have exited.
+    exits: HashSet<B256>,
+    /// The number of deposits made so far.
+    deposits: u64,
+    /// Channel for emitting deposit events.
+    events: broadcast::Sender<Deposit>,
+}
+
+impl Default for L1 {
+    fn default() -> Self {
+        Self {
+            batches: vec![],
+            exits: HashSet::new(),
+            deposits: 0,
+            events: broadcast::channel(64).0,
+        }
+    }
+}
+
+impl L1 {
+    /// Commits the roots of the next batch.
+    pub fn commit(&mut self, commitment: BatchCommitment) -> Result<(), L1Error> {
+        let expected = self.batches.len() as u64;
+        if commitment.index != expected {
+            return Err(L1Error::UnexpectedBatch {
+                expected,
+                actual: commitment.index,
+            });
+        }
+        self.batches.push(commitment);
+        Ok(())
+    }
+
+    /// Returns the batch committed at the given index.
+    pub fn batch(&self, index: u64) -> Option<&BatchCommitment> {
+        self.batches.get(index as usize)
+    }
+
+    /// Finalizes the exit of a withdrawal proven against the withdrawals root of the given batch.
+    pub fn finalize_withdrawal(&mut self, batch: u64, proof: &MerkleProof) -> Result<(), L1Error> {
+        let commitment = self.batch(batch).ok_or(L1Error::UnknownBatch(batch))?;
+        if !proof.verify(&commitment.roots.withdrawals_root) {
+            return Err(L1Error::InvalidProof);
+        }
+        if !self.exits.insert(proof.leaf) {
+            return Err(L1Error::AlreadyFinalized);
+        }
+        Ok(())
+    }
+
+    /// Returns whether the withdrawal with the given hash has exited.
+    pub fn is_finalized(&self, withdrawal: B256) -> bool {
+        self.exits.contains(&withdrawal)
+    }
+
+    /// Deposits funds to a recipient on the L2 and emits the deposit event.
+    pub fn deposit(&mut self, recipient: Address, amount: u64) -> Deposit {
+        let deposit = Deposit {
+            index: self.deposits,
+            recipient,
+            amount,
+        };
+        self.deposits += 1;
+        // There may be no subscribers.
+        let _ = self.events.send(deposit.clone());
+        deposit
+    }
+
+    /// Subscribes to the deposit events emitted from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Deposit> {
+        self.events.subscribe()
+    }
+}
+
+/// Commits the roots of every finalized block to the L1, one block per batch. Blocks missed
+/// while lagging behind the finalized blocks are read back from the chain, so that no batch
+/// is ever skipped.
+pub async fn settle(
+    l1: Arc<Mutex<L1>>,
+    chain: Arc<Mutex<Blockchain>>,
+    mut blocks: broadcast::Receiver<Block>,
+) {
+    loop {
+        let head = match blocks.recv().await {
+            Ok(block) => block.number(),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!(
+                    "Missed {} finalized blocks, reading them from the chain",
+                    skipped
+                );
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        let next = l1.lock().await.batches.len() as u64;
+        for number in next..=head {
+            let header = match chain.lock().await.header(number) {
+                Ok(Some(header)) => header,
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Failed to read header {}: {}", number, e);
+                    break;
+                }
+            };
+            let commitment = BatchCommitment {
+                index: number,
+                roots: header.header().state_roots(),
+            };
+            match l1.lock().await.commit(commitment) {
+                Ok(()) => info!("Committed batch: {}", number),
+                Err(e) => {
+                    warn!("Failed to commit batch {}: {}", number, e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rollup::test_utils::certified_block;
+    use rollup::{SignedTransaction, Signer, State, Transaction};
+
+    /// Seals and finalizes the next block of the chain with a single withdrawal.
+    fn seal(chain: &mut Blockchain, signer: &Signer) -> Block {
+        let withdrawal = Transaction::withdrawal(signer.address, 1, chain.height(), 1);
+        let transactions = vec![SignedTransaction::new(withdrawal, signer)];
+        let block = certified_block(chain, signer, transactions);
+        chain.import(block.clone()).unwrap();
+        block
+    }
+
+    #[test]
+    fn test_finalize_withdrawal() {
+        let signer = Signer::random();
+        let withdrawal =
+            SignedTransaction::new(Transaction::withdrawal(signer.address, 1, 0, 1), &signer);
+        let mut state = State::default();
+        let mut l1 = L1::default();
+        l1.commit(BatchCommitment {
+            index: 0,
+            roots: state.roots(),
+        })
+        .unwrap();
+
+        // Batches are committed in order.
+        state.apply(std::slice::from_ref(&withdrawal));
+        let commitment = BatchCommitment {
+            index: 2,
+            roots: state.roots(),
+        };
+        assert_eq!(
+            l1.commit(commitment.clone()),
+            Err(L1Error::UnexpectedBatch {
+                expected: 1,
+                actual: 2
+            })
+        );
+        l1.commit(BatchCommitment {
+            index: 1,
+            ..commitment
+        })
+        .unwrap();
+
+        // The withdrawal can only exit against a batch that includes it, and only once.
+        let hash = withdrawal.transaction.hash();
+        let proof = MerkleProof::new(&[hash], 0).unwrap();
+        assert_eq!(
+            l1.finalize_withdrawal(0, &proof),
+            Err(L1Error::InvalidProof)
+        );
+        assert_eq!(
+            l1.finalize_withdrawal(2, &proof),
+            Err(L1Error::UnknownBatch(2))
+        );
+        assert!(!l1.is_finalized(hash));
+        l1.finalize_withdrawal(1, &proof).unwrap();
+        assert!(l1.is_finalized(hash));
+        assert_eq!(
+            l1.finalize_withdrawal(1, &proof),
+            Err(L1Error::AlreadyFinalized)
+        );
+    }
+
+    #[test]
+    fn test_deposit_events() {
+        let mut l1 = L1::default();
+        let mut events = l1.subscribe();
+        let recipient = Address::random();
+        let first = l1.deposit(recipient, 5);
+        let second = l1.deposit(recipient, 7);
+        assert_eq!((first.index, second.index), (0, 1));
+        assert_eq!(events.try_recv().unwrap(), first);
+        assert_eq!(events.try_recv().unwrap(), second);
+    }
+
+    #[tokio::test]
+    async fn test_settle_backfills_missed_blocks() {
+        let signer = Signer::random();
+        let mut chain = Blockchain::new(vec![signer.address]);
+
+        // The receiver lags behind all but the last of the finalized blocks.
+        let (sender, blocks) = broadcast::channel(1);
+        for _ in 0..3 {
+            sender.send(seal(&mut chain, &signer)).unwrap();
+        }
+        drop(sender);
+
+        // Every block is committed in order nonetheless.
+        let l1 = Arc::new(Mutex::new(L1::default()));
+        let chain = Arc::new(Mutex::new(chain));
+        settle(l1.clone(), chain.clone(), blocks).await;
+        let l1 = l1.lock().await;
+        let chain = chain.lock().await;
+        for number in 0..3 {
+            let roots = chain
+                .header(number)
+                .unwrap()
+                .unwrap()
+                .header()
+                .state_roots();
+            assert_eq!(l1.batch(number).unwrap().roots, roots);
+        }
+        assert!(l1.batch(3).is_none());
+    }
+}
@@ -9,6 +9,28 @@ use tokio::{io, select, task};
 
 pub use gossipsub::Message as GossipMessage;
 
+/// The topic of finalized blocks, gossiped by the sequencer that sealed them.
+pub const BLOCKS_TOPIC: &str = "blocks";
+/// The topic of transactions forwarded to the sequencers.
+pub const TRANSACTIONS_TOPIC: &str = "transactions";
+/// The topic of blocks proposed for consensus.
+pub const PROPOSALS_TOPIC: &str = "proposals";
+/// The topic of consensus votes.
+pub const VOTES_TOPIC: &str = "votes";
+/// The topic of equivocation evidence.
+pub const EVIDENCE_TOPIC: &str = "evidence";
+/// The topic of preconfirmations of pending transactions.
+pub const PRECONFIRMATIONS_TOPIC: &str = "preconfirmations";
+/// Every topic the nodes of the network subscribe to.
+pub const TOPICS: [&str; 6] = [
+    BLOCKS_TOPIC,
+    TRANSACTIONS_TOPIC,
+    PROPOSALS_TOPIC,
+    VOTES_TOPIC,
+    EVIDENCE_TOPIC,
+    PRECONFIRMATIONS_TOPIC,
+];
+
 // We create a custom network behaviour that combines Gossipsub and Mdns.
 #[derive(NetworkBehaviour)]
 struct MyBehaviour {
@@ -45,7 +67,7 @@ impl Network {
                     .validation_mode(gossipsub::ValidationMode::Strict) // This sets the kind of message validation. The default is Strict (enforce message signing)
                     .message_id_fn(message_id_fn) // content-address messages. No two messages of the same content will be propagated.
                     .build()
-                    .map_err(|msg| io::Error::new(io::ErrorKind::Other, msg))?; // Temporary hack because `build` does not return a proper `std::error::Error`.
+                    .map_err(io::Error::other)?; // Temporary hack because `build` does not return a proper `std::error::Error`.
 
                 // build a gossipsub network behaviour
                 let gossipsub = gossipsub::Behaviour::new(
@@ -62,11 +84,11 @@ impl Network {
             .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
             .build();
 
-        // Create a Gossipsub topic
-        let topic = gossipsub::IdentTopic::new("blocks");
-        swarm.behaviour_mut().gossipsub.subscribe(&topic)?;
-        let topic = gossipsub::IdentTopic::new("transactions");
-        swarm.behaviour_mut().gossipsub.subscribe(&topic)?;
+        // Subscribe to the Gossipsub topics
+        for topic in TOPICS {
+            let topic = gossipsub::IdentTopic::new(topic);
+            swarm.behaviour_mut().gossipsub.subscribe(&topic)?;
+        }
 
         // Listen on all interfaces and whatever port the OS assigns
         swarm.listen_on("/ip4/0.0.0.0/udp/0/quic-v1".parse()?)?;
@@ -83,8 +105,10 @@ impl Network {
                 select! {
                     Some((data, topic)) = outbound.recv() => {
                         let topic = gossipsub::IdentTopic::new(topic);
-                        if let Err(e) = network.swarm.behaviour_mut().gossipsub.publish(topic, data) {
-                            println!("Failed to publish message: {e}");
+                        match network.swarm.behaviour_mut().gossipsub.publish(topic, data) {
+                            // Re-gossiped messages that were already published are expected.
+                            Ok(_) | Err(gossipsub::PublishError::Duplicate) => {}
+                            Err(e) => println!("Failed to publish message: {e}"),
                         }
                     },
                     event = network.swarm.select_next_some() => match event {
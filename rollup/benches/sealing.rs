@@ -0,0 +1,89 @@
+//! Measures the block period sustained by a sole sequencer sealing every 100ms while
+//! transactions are continuously submitted to it.
+//!
+//! Run with `cargo bench -p rollup --bench sealing`.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rollup::{
+    Blockchain, EvidenceStore, Mempool, Sequencer, SignedTransaction, Signer, Transaction,
+};
+use tokio::sync::{mpsc, Mutex};
+
+/// The configured interval of time between blocks.
+const BLOCK_PERIOD: Duration = Duration::from_millis(100);
+/// The number of block periods to measure.
+const BLOCKS: usize = 100;
+/// The number of transactions submitted every block period.
+const TRANSACTIONS_PER_BLOCK: u64 = 50;
+
+#[tokio::main]
+async fn main() {
+    let signer = Signer::random();
+    let chain = Arc::new(Mutex::new(Blockchain::new(vec![signer.address])));
+    let (_tx_in, rx_in) = mpsc::channel(32);
+    let (tx_out, mut rx_out) = mpsc::channel(1024);
+    let evidence = Arc::new(Mutex::new(EvidenceStore::default()));
+    let mut sequencer = Sequencer::new(
+        signer,
+        Arc::new(Mutex::new(Mempool::default())),
+        chain,
+        rx_in,
+        tx_out,
+        evidence,
+    )
+    .with_block_period(BLOCK_PERIOD);
+    let submitter = sequencer.submitter();
+    let mut blocks = sequencer.subscribe();
+
+    // Discard the gossiped messages as there are no peers.
+    tokio::spawn(async move { while rx_out.recv().await.is_some() {} });
+    tokio::spawn(async move { sequencer.run().await });
+
+    // Submit transactions at a steady rate.
+    tokio::spawn(async move {
+        let sender = Signer::random();
+        let mut nonce = 0;
+        loop {
+            for _ in 0..TRANSACTIONS_PER_BLOCK {
+                let tx = Transaction::dynamic(sender.address, 1, nonce);
+                let tx = SignedTransaction::new(tx, &sender);
+                submitter.submit(tx).await.unwrap();
+                nonce += 1;
+            }
+            tokio::time::sleep(BLOCK_PERIOD).await;
+        }
+    });
+
+    // Record the timestamps of the finalized blocks.
+    let start = Instant::now();
+    let mut timestamps = vec![];
+    let mut transactions = 0;
+    while timestamps.len() <= BLOCKS {
+        let block = blocks.recv().await.unwrap();
+        timestamps.push(block.header().timestamp);
+        transactions += block.transactions().len();
+    }
+    let elapsed = start.elapsed();
+
+    let periods: Vec<u64> = timestamps.windows(2).map(|w| w[1] - w[0]).collect();
+    let mean = periods.iter().sum::<u64>() as f64 / periods.len() as f64;
+    let max = periods.iter().max().unwrap();
+    println!(
+        "Sealed {} blocks with {} transactions in {:?}",
+        timestamps.len(),
+        transactions,
+        elapsed
+    );
+    println!(
+        "Block period: target {}ms, mean {:.1}ms, max {}ms",
+        BLOCK_PERIOD.as_millis(),
+        mean,
+        max
+    );
+    assert!(
+        mean < BLOCK_PERIOD.as_millis() as f64 * 1.1,
+        "failed to sustain the block period"
+    );
+}
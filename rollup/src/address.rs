@@ -1,10 +1,11 @@
 use alloy_primitives::{keccak256, Address as AlloyAddress};
 use secp256k1::PublicKey;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
 /// A newtype wrapper around an Ethereum address.
 /// Allows conversion from a public key.
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Address(AlloyAddress);
 
 impl From<PublicKey> for Address {
@@ -21,3 +22,18 @@ impl Address {
         Address(AlloyAddress::random())
     }
 }
+
+/// Parses an [Address] from a hex string.
+impl FromStr for Address {
+    type Err = <AlloyAddress as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AlloyAddress::from_str(s).map(Address)
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
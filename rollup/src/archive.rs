@@ -0,0 +1,247 @@
+use std::io::{self, BufReader, Read, Write};
+
+use crate::{Block, Blockchain, ImportError, StorageError};
+
+/// The bytes opening every archive, identifying the format.
+const MAGIC: &[u8; 4] = b"NLMA";
+/// The flag marking an archive whose blocks are compressed with zstd.
+const COMPRESSED: u8 = 1;
+/// The maximum size of an encoded block, guarding against corrupt length prefixes.
+const MAX_BLOCK_SIZE: u64 = 64 << 20;
+
+/// An error encountered while exporting or importing an archive of blocks.
+#[derive(Debug)]
+pub enum ArchiveError {
+    /// The archive could not be read or written.
+    Io(io::Error),
+    /// The archive is not in the expected format.
+    InvalidFormat(String),
+    /// The range to export ends before it starts.
+    InvalidRange { from: u64, to: u64 },
+    /// A block in the range to export does not exist.
+    MissingBlock(u64),
+    /// A block to export could not be read from storage.
+    Storage(StorageError),
+    /// A block in the archive could not be imported.
+    Import(ImportError),
+}
+
+impl std::fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveError::Io(e) => write!(f, "failed to access archive: {e}"),
+            ArchiveError::InvalidFormat(e) => write!(f, "invalid archive: {e}"),
+            ArchiveError::InvalidRange { from, to } => {
+                write!(f, "invalid range of blocks {from} to {to}")
+            }
+            ArchiveError::MissingBlock(number) => write!(f, "missing block {number}"),
+            ArchiveError::Storage(e) => write!(f, "failed to read block: {e}"),
+            ArchiveError::Import(e) => write!(f, "failed to import block: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+impl From<io::Error> for ArchiveError {
+    fn from(e: io::Error) -> Self {
+        ArchiveError::Io(e)
+    }
+}
+
+impl From<StorageError> for ArchiveError {
+    fn from(e: StorageError) -> Self {
+        ArchiveError::Storage(e)
+    }
+}
+
+impl From<ImportError> for ArchiveError {
+    fn from(e: ImportError) -> Self {
+        ArchiveError::Import(e)
+    }
+}
+
+/// Writes the blocks of the chain numbered `from` to `to` inclusive to an archive, optionally
+/// compressing them with zstd. The archive opens with a header of the format's magic bytes,
+/// a flags byte and the number of blocks, followed by each block canonically encoded and
+/// prefixed with its length. Returns the number of blocks exported.
+pub fn export(
+    chain: &Blockchain,
+    from: u64,
+    to: u64,
+    mut writer: impl Write,
+    compress: bool,
+) -> Result<u64, ArchiveError> {
+    if from > to {
+        return Err(ArchiveError::InvalidRange { from, to });
+    }
+    if to >= chain.height() {
+        return Err(ArchiveError::MissingBlock(chain.height().max(from)));
+    }
+    let count = to - from + 1;
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[if compress { COMPRESSED } else { 0 }])?;
+    writer.write_all(&count.to_le_bytes())?;
+    if compress {
+        let mut encoder = zstd::Encoder::new(writer, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+        write_blocks(chain, from, to, &mut encoder)?;
+        encoder.finish()?.flush()?;
+    } else {
+        write_blocks(chain, from, to, &mut writer)?;
+        writer.flush()?;
+    }
+    Ok(count)
+}
+
+/// Writes the length-prefixed encodings of the blocks in the range.
+fn write_blocks(
+    chain: &Blockchain,
+    from: u64,
+    to: u64,
+    writer: &mut impl Write,
+) -> Result<(), ArchiveError> {
+    for number in from..=to {
+        let block = chain
+            .block(number)?
+            .ok_or(ArchiveError::MissingBlock(number))?;
+        let bytes = bincode::serialize(&block).map_err(io::Error::other)?;
+        writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+        writer.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+/// Reads the blocks of an archive written by [export] and imports them into the chain,
+/// validating every block against the chain as extended by the ones before it. The first
+/// block must follow the head of the chain. The whole archive is read and validated before
+/// any block is imported, so a corrupt archive leaves the chain unchanged. Returns the number
+/// of blocks imported.
+pub fn import(chain: &mut Blockchain, reader: impl Read) -> Result<u64, ArchiveError> {
+    let mut reader = BufReader::new(reader);
+    let mut header = [0; 13];
+    reader.read_exact(&mut header)?;
+    if &header[..4] != MAGIC {
+        return Err(ArchiveError::InvalidFormat(
+            "unknown magic bytes".to_string(),
+        ));
+    }
+    let count = u64::from_le_bytes(header[5..].try_into().unwrap());
+    match header[4] {
+        0 => read_blocks(chain, count, reader)?,
+        COMPRESSED => read_blocks(chain, count, zstd::Decoder::with_buffer(reader)?)?,
+        flags => {
+            return Err(ArchiveError::InvalidFormat(format!(
+                "unknown flags {flags}"
+            )))
+        }
+    }
+    Ok(count)
+}
+
+/// Reads the given number of length-prefixed blocks and imports them into the chain.
+fn read_blocks(
+    chain: &mut Blockchain,
+    count: u64,
+    mut reader: impl Read,
+) -> Result<(), ArchiveError> {
+    let mut blocks = vec![];
+    for _ in 0..count {
+        let mut len = [0; 8];
+        reader.read_exact(&mut len)?;
+        let len = u64::from_le_bytes(len);
+        if len > MAX_BLOCK_SIZE {
+            let e = format!("block of {len} bytes exceeds the maximum size");
+            return Err(ArchiveError::InvalidFormat(e));
+        }
+        let mut bytes = vec![0; len as usize];
+        reader.read_exact(&mut bytes)?;
+        let block: Block =
+            bincode::deserialize(&bytes).map_err(|e| ArchiveError::InvalidFormat(e.to_string()))?;
+        blocks.push(block);
+    }
+    chain.import_all(blocks)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::certified_block;
+    use crate::{SignedTransaction, Signer, Transaction};
+
+    /// Seals and finalizes the next block of the chain with a single transaction.
+    fn seal(chain: &mut Blockchain, signer: &Signer) {
+        let transfer = Transaction::dynamic(signer.address, 1, chain.height());
+        let transactions = vec![SignedTransaction::new(transfer, signer)];
+        chain
+            .import(certified_block(chain, signer, transactions))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_export_import() {
+        let signer = Signer::random();
+        let mut chain = Blockchain::new(vec![signer.address]);
+        for _ in 0..4 {
+            seal(&mut chain, &signer);
+        }
+
+        // The exported chain is reproduced by a fresh node, compressed or not.
+        for compress in [false, true] {
+            let mut archive = vec![];
+            assert_eq!(export(&chain, 0, 3, &mut archive, compress).unwrap(), 4);
+            let mut imported = Blockchain::new(vec![signer.address]);
+            assert_eq!(import(&mut imported, archive.as_slice()).unwrap(), 4);
+            assert_eq!(imported.head(), chain.head());
+        }
+
+        // A range can only be imported after the block preceding it.
+        let mut archive = vec![];
+        export(&chain, 2, 3, &mut archive, true).unwrap();
+        let mut fresh = Blockchain::new(vec![signer.address]);
+        assert!(matches!(
+            import(&mut fresh, archive.as_slice()),
+            Err(ArchiveError::Import(ImportError::UnexpectedNumber { .. }))
+        ));
+
+        // Blocks are validated against the importing chain, and truncation is detected.
+        let mut archive = vec![];
+        export(&chain, 0, 3, &mut archive, false).unwrap();
+        let mut other = Blockchain::new(vec![Signer::random().address]);
+        assert!(matches!(
+            import(&mut other, archive.as_slice()),
+            Err(ArchiveError::Import(ImportError::WrongSequencer { .. }))
+        ));
+        let mut fresh = Blockchain::new(vec![signer.address]);
+        assert!(matches!(
+            import(&mut fresh, &archive[..archive.len() - 1]),
+            Err(ArchiveError::Io(_))
+        ));
+        assert_eq!(fresh.height(), 0);
+
+        // No block is imported from an archive with an invalid block, however late.
+        let mut archive = MAGIC.to_vec();
+        archive.push(0);
+        archive.extend(3u64.to_le_bytes());
+        for number in [0, 1, 3] {
+            let bytes = bincode::serialize(&chain.block(number).unwrap().unwrap()).unwrap();
+            archive.extend((bytes.len() as u64).to_le_bytes());
+            archive.extend(bytes);
+        }
+        assert!(matches!(
+            import(&mut fresh, archive.as_slice()),
+            Err(ArchiveError::Import(ImportError::UnexpectedNumber { .. }))
+        ));
+        assert_eq!(fresh.height(), 0);
+
+        // Only ranges of existing blocks in ascending order are exported.
+        assert!(matches!(
+            export(&chain, 3, 4, vec![], false),
+            Err(ArchiveError::MissingBlock(4))
+        ));
+        assert!(matches!(
+            export(&chain, 3, 2, vec![], false),
+            Err(ArchiveError::InvalidRange { from: 3, to: 2 })
+        ));
+    }
+}
@@ -0,0 +1,362 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, Mutex};
+
+use crate::{Address, Block, Blockchain, ImportError};
+
+/// A batch of consecutive finalized blocks posted to a data availability layer.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Batch {
+    /// The index of the batch.
+    pub index: u64,
+    /// The blocks in the batch, in block order.
+    pub blocks: Vec<Block>,
+}
+
+impl Batch {
+    /// Encodes the batch canonically and compresses it with zstd.
+    pub fn encode(&self) -> io::Result<Vec<u8>> {
+        let bytes = bincode::serialize(self).map_err(io::Error::other)?;
+        zstd::encode_all(bytes.as_slice(), zstd::DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    /// Decompresses and decodes a batch encoded with [Batch::encode].
+    pub fn decode(data: &[u8]) -> io::Result<Self> {
+        let bytes = zstd::decode_all(data)?;
+        bincode::deserialize(&bytes).map_err(io::Error::other)
+    }
+}
+
+/// A data availability layer that encoded batches are posted to and retrieved from by index.
+pub trait DaSink {
+    /// Posts the encoded batch with the given index.
+    fn post(&mut self, index: u64, data: &[u8]) -> io::Result<()>;
+
+    /// Retrieves the encoded batch with the given index, if it has been posted.
+    fn get(&self, index: u64) -> io::Result<Option<Vec<u8>>>;
+
+    /// Returns the number of batches posted, which are indexed consecutively from zero.
+    fn posted(&self) -> io::Result<u64> {
+        let mut index = 0;
+        while self.get(index)?.is_some() {
+            index += 1;
+        }
+        Ok(index)
+    }
+}
+
+/// A [DaSink] standing in for a data availability layer by storing batches as files
+/// in a directory.
+pub struct FileSink {
+    /// The directory the batches are stored in.
+    dir: PathBuf,
+}
+
+impl FileSink {
+    /// Opens the sink storing batches in the given directory, creating it if necessary.
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Returns the path of the file storing the batch with the given index.
+    fn path(&self, index: u64) -> PathBuf {
+        self.dir.join(format!("batch-{index:08}.zst"))
+    }
+}
+
+impl DaSink for FileSink {
+    fn post(&mut self, index: u64, data: &[u8]) -> io::Result<()> {
+        // Write to a temporary file first so that a batch is never partially visible.
+        let tmp = self.path(index).with_extension("tmp");
+        fs::write(&tmp, data)?;
+        fs::rename(tmp, self.path(index))
+    }
+
+    fn get(&self, index: u64) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(index)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn posted(&self) -> io::Result<u64> {
+        let mut index = 0;
+        while self.path(index).try_exists()? {
+            index += 1;
+        }
+        Ok(index)
+    }
+}
+
+/// Groups consecutive finalized blocks into batches and posts them to a [DaSink].
+pub struct BatchSubmitter<S> {
+    /// The sink batches are posted to.
+    sink: S,
+    /// The number of blocks in each batch.
+    batch_size: usize,
+    /// The blocks yet to be posted.
+    pending: Vec<Block>,
+    /// The index of the next batch.
+    index: u64,
+    /// The number of the next block to batch.
+    next: u64,
+}
+
+impl<S: DaSink> BatchSubmitter<S> {
+    /// Creates a new [BatchSubmitter] posting batches of the given number of blocks, continuing
+    /// after the batches already posted to the sink.
+    pub fn new(sink: S, batch_size: usize) -> io::Result<Self> {
+        assert!(batch_size > 0, "batches must contain at least one block");
+        let index = sink.posted()?;
+        let next = match index.checked_sub(1) {
+            Some(last) => {
+                let data = sink.get(last)?.ok_or(io::ErrorKind::NotFound)?;
+                let batch = Batch::decode(&data)?;
+                batch.blocks.last().map_or(0, |block| block.number() + 1)
+            }
+            None => 0,
+        };
+        Ok(Self {
+            sink,
+            batch_size,
+            pending: vec![],
+            index,
+            next,
+        })
+    }
+
+    /// Adds the next finalized block, posting the pending blocks as a batch once there are
+    /// enough. The blocks of a batch that fails to post are kept and posted again with the
+    /// next block.
+    pub fn add(&mut self, block: Block) -> io::Result<()> {
+        self.next = block.number() + 1;
+        self.pending.push(block);
+        while self.pending.len() >= self.batch_size {
+            let batch = Batch {
+                index: self.index,
+                blocks: self.pending.drain(..self.batch_size).collect(),
+            };
+            if let Err(e) = batch
+                .encode()
+                .and_then(|data| self.sink.post(batch.index, &data))
+            {
+                self.pending.splice(0..0, batch.blocks);
+                return Err(e);
+            }
+            info!(
+                "Posted batch {} of {} blocks",
+                batch.index,
+                batch.blocks.len()
+            );
+            self.index += 1;
+        }
+        Ok(())
+    }
+
+    /// Runs the submitter, batching the finalized blocks received from the sequencer. Blocks
+    /// missed while lagging behind or before a restart are read back from the chain.
+    pub async fn run(
+        mut self,
+        chain: Arc<Mutex<Blockchain>>,
+        mut blocks: broadcast::Receiver<Block>,
+    ) {
+        loop {
+            let block = match blocks.recv().await {
+                Ok(block) => block,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(
+                        "Missed {} finalized blocks, reading them from the chain",
+                        skipped
+                    );
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            while self.next <= block.number() {
+                let next = if self.next < block.number() {
+                    match chain.lock().await.block(self.next) {
+                        Ok(next) => next,
+                        Err(e) => {
+                            warn!("Failed to read block {}: {}", self.next, e);
+                            break;
+                        }
+                    }
+                } else {
+                    Some(block.clone())
+                };
+                let Some(next) = next else {
+                    break;
+                };
+                if let Err(e) = self.add(next) {
+                    warn!("Failed to post batch {}: {}", self.index, e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// An error encountered while deriving the chain from a data availability layer.
+#[derive(Debug)]
+pub enum DeriveError {
+    /// A batch could not be retrieved or decoded.
+    Io(io::Error),
+    /// A batch was posted under a different index than it records.
+    UnexpectedBatch { expected: u64, actual: u64 },
+    /// A block in a batch could not be imported.
+    Import(ImportError),
+}
+
+impl std::fmt::Display for DeriveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeriveError::Io(e) => write!(f, "failed to read batch: {e}"),
+            DeriveError::UnexpectedBatch { expected, actual } => {
+                write!(f, "unexpected batch index {actual}, expected {expected}")
+            }
+            DeriveError::Import(e) => write!(f, "failed to import block: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DeriveError {}
+
+impl From<io::Error> for DeriveError {
+    fn from(e: io::Error) -> Self {
+        DeriveError::Io(e)
+    }
+}
+
+impl From<ImportError> for DeriveError {
+    fn from(e: ImportError) -> Self {
+        DeriveError::Import(e)
+    }
+}
+
+/// Reconstructs the blockchain sealed by the given sequencers purely from the batches
+/// posted to the sink, validating every block as it is imported.
+pub fn derive(sink: &impl DaSink, sequencers: Vec<Address>) -> Result<Blockchain, DeriveError> {
+    let mut chain = Blockchain::new(sequencers);
+    let mut index = 0;
+    while let Some(data) = sink.get(index)? {
+        let batch = Batch::decode(&data)?;
+        if batch.index != index {
+            return Err(DeriveError::UnexpectedBatch {
+                expected: index,
+                actual: batch.index,
+            });
+        }
+        for block in batch.blocks {
+            chain.import(block)?;
+        }
+        index += 1;
+    }
+    Ok(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::certified_block;
+    use crate::{SignedTransaction, Signer, Transaction};
+
+    /// Seals and finalizes the next block of the chain with a single transaction.
+    fn seal(chain: &mut Blockchain, signer: &Signer) -> Block {
+        let withdrawal = Transaction::withdrawal(signer.address, 1, chain.height(), 1);
+        let transactions = vec![SignedTransaction::new(withdrawal, signer)];
+        let block = certified_block(chain, signer, transactions);
+        chain.import(block.clone()).unwrap();
+        block
+    }
+
+    #[test]
+    fn test_derive() {
+        let signer = Signer::random();
+        let mut chain = Blockchain::new(vec![signer.address]);
+        let dir = std::env::temp_dir().join(format!("batches-{}", signer.address));
+        let mut submitter = BatchSubmitter::new(FileSink::open(&dir).unwrap(), 2).unwrap();
+        for _ in 0..5 {
+            submitter.add(seal(&mut chain, &signer)).unwrap();
+        }
+
+        // Only complete batches are posted.
+        let sink = FileSink::open(&dir).unwrap();
+        let batch = Batch::decode(&sink.get(1).unwrap().unwrap()).unwrap();
+        assert_eq!(
+            batch.blocks,
+            [
+                chain.block(2).unwrap().unwrap(),
+                chain.block(3).unwrap().unwrap()
+            ]
+        );
+        assert!(sink.get(2).unwrap().is_none());
+
+        // The chain is reconstructed from the posted batches alone.
+        let derived = derive(&sink, vec![signer.address]).unwrap();
+        assert_eq!(derived.head(), chain.block(3).unwrap());
+        assert_eq!(
+            derived.state.roots(),
+            chain.block(3).unwrap().unwrap().header().state_roots()
+        );
+
+        // Blocks sealed by other sequencers are rejected.
+        assert!(matches!(
+            derive(&sink, vec![Signer::random().address]),
+            Err(DeriveError::Import(ImportError::WrongSequencer { .. }))
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_posts_and_restarts() {
+        let signer = Signer::random();
+        let mut chain = Blockchain::new(vec![signer.address]);
+        let dir = std::env::temp_dir().join(format!("retries-{}", signer.address));
+        let mut submitter = BatchSubmitter::new(FileSink::open(&dir).unwrap(), 2).unwrap();
+
+        // The blocks of a batch that fails to post are kept and posted with the next block.
+        submitter.add(seal(&mut chain, &signer)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(submitter.add(seal(&mut chain, &signer)).is_err());
+        fs::create_dir_all(&dir).unwrap();
+        submitter.add(seal(&mut chain, &signer)).unwrap();
+        let sink = FileSink::open(&dir).unwrap();
+        let batch = Batch::decode(&sink.get(0).unwrap().unwrap()).unwrap();
+        assert_eq!(
+            batch.blocks,
+            [
+                chain.block(0).unwrap().unwrap(),
+                chain.block(1).unwrap().unwrap()
+            ]
+        );
+
+        // A restarted submitter continues after the posted batches, reading back the pending
+        // block it lost from the chain.
+        let head = seal(&mut chain, &signer);
+        let (sender, blocks) = broadcast::channel(1);
+        sender.send(head).unwrap();
+        drop(sender);
+        let submitter = BatchSubmitter::new(FileSink::open(&dir).unwrap(), 2).unwrap();
+        assert_eq!((submitter.index, submitter.next), (1, 2));
+        let chain = Arc::new(Mutex::new(chain));
+        submitter.run(chain.clone(), blocks).await;
+        let batch = Batch::decode(&sink.get(1).unwrap().unwrap()).unwrap();
+        let chain = chain.lock().await;
+        assert_eq!(
+            batch.blocks,
+            [
+                chain.block(2).unwrap().unwrap(),
+                chain.block(3).unwrap().unwrap()
+            ]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
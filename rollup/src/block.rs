@@ -1,8 +1,9 @@
 use alloy_primitives::{keccak256, B256};
-use secp256k1::{Message, Secp256k1};
 use serde::{Deserialize, Serialize};
 
-use crate::{Address, Signature, SignedTransaction, Signer};
+use crate::{
+    Address, CommitCertificate, Signature, SignedHandover, SignedTransaction, Signer, StateRoots,
+};
 
 /// A block header containing metadata about the block.
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
@@ -11,14 +12,19 @@ pub struct BlockHeader {
     pub sequencer: Address,
     /// The number of the block.
     pub number: u64,
-    /// The timestamp at the time the block was sealed.
+    /// The view the block was proposed in, i.e. the number of times its slot passed on to the
+    /// next sequencer after the scheduled one failed to get a block finalized.
+    pub view: u64,
+    /// The time the block was sealed, in milliseconds since the Unix epoch.
     pub timestamp: u64,
     /// The hash of the parent block. None if this is the genesis block.
     pub parent_digest: Option<B256>,
-    /// The root digest of the withdrawals Merkle tree.
+    /// The root digest of the withdrawals Merkle tree after applying the block's transactions.
     pub withdrawals_root: String,
-    /// The root digest of the transactions Merkle tree.
+    /// The root digest of the transactions Merkle tree after applying the block's transactions.
     pub transactions_root: String,
+    /// A handover of sequencing rights to a new sequencer, if one was recorded in this block.
+    pub handover: Option<SignedHandover>,
 }
 
 impl BlockHeader {
@@ -27,6 +33,14 @@ impl BlockHeader {
         let bytes = bincode::serialize(self).unwrap();
         keccak256(bytes)
     }
+
+    /// Returns the state roots committed to by the block header.
+    pub fn state_roots(&self) -> StateRoots {
+        StateRoots {
+            withdrawals_root: self.withdrawals_root.clone(),
+            transactions_root: self.transactions_root.clone(),
+        }
+    }
 }
 
 /// A signed block header containing a block header and a signature.
@@ -42,13 +56,26 @@ impl SignedBlockHeader {
         let signature = signer.sign(header.hash());
         Self { header, signature }
     }
+
+    /// Returns the header that was signed.
+    pub fn header(&self) -> &BlockHeader {
+        &self.header
+    }
+
+    /// Verifies the signature of the [SignedBlockHeader] is valid and that it matches
+    /// the sequencer address specified in the [BlockHeader].
+    pub fn verify(&self) -> bool {
+        self.signature.recover(self.header.hash()) == Some(self.header.sequencer)
+    }
 }
 
-/// A block containing a header and a list of transactions.
+/// A block containing a header, a list of transactions and, once finalized,
+/// the commit certificate of the sequencer committee.
 #[derive(Serialize, Deserialize, Clone, Eq, PartialEq)]
 pub struct Block {
     pub(crate) signed: SignedBlockHeader,
     pub(crate) transactions: Vec<SignedTransaction>,
+    pub(crate) certificate: Option<CommitCertificate>,
 }
 
 impl std::fmt::Debug for Block {
@@ -63,6 +90,7 @@ impl std::fmt::Debug for Block {
                     .map(|tx| tx.transaction.hash())
                     .collect::<Vec<_>>(),
             )
+            .field("certificate", &self.certificate)
             .finish()
     }
 }
@@ -73,9 +101,23 @@ impl Block {
         Block {
             signed: header,
             transactions,
+            certificate: None,
         }
     }
 
+    /// Attaches the commit certificate finalizing the block.
+    pub fn with_certificate(self, certificate: CommitCertificate) -> Self {
+        Block {
+            certificate: Some(certificate),
+            ..self
+        }
+    }
+
+    /// Returns the commit certificate finalizing the block, if it has been finalized.
+    pub fn certificate(&self) -> Option<&CommitCertificate> {
+        self.certificate.as_ref()
+    }
+
     /// Computes the hash of the block.
     pub fn hash(&self) -> B256 {
         let bytes = bincode::serialize(&self.signed.header).unwrap();
@@ -84,27 +126,44 @@ impl Block {
 
     /// Verifies the signature of the [Block] is valid and that it matches
     /// the sequencer address specified in the [SignedBlockHeader].
+    /// Any recorded handover must be signed by that same sequencer and take
+    /// effect after this block.
     pub fn verify(&self) -> bool {
-        let secp = Secp256k1::new();
-        let msg = Message::from_digest(self.hash().into());
-        let pk = secp
-            .recover_ecdsa(&msg, &(&self.signed.signature).into())
-            .unwrap();
-        let address = Address::from(pk);
-        secp.verify_ecdsa(&msg, &(&self.signed.signature).into(), &pk)
-            .is_ok()
-            && self.signed.header.sequencer == address
+        let sequencer = self.signed.header.sequencer;
+        self.signed.verify()
+            && self.signed.header.handover.as_ref().is_none_or(|h| {
+                h.verify()
+                    && h.handover().current == sequencer
+                    && h.handover().effective_at > self.number()
+            })
     }
 
     /// Returns the number of the block.
     pub fn number(&self) -> u64 {
         self.signed.header.number
     }
+
+    /// Returns the transactions sealed into the block.
+    pub fn transactions(&self) -> &[SignedTransaction] {
+        &self.transactions
+    }
+
+    /// Returns the header of the block.
+    pub fn header(&self) -> &BlockHeader {
+        &self.signed.header
+    }
+
+    /// Returns the signed header of the block.
+    pub fn signed(&self) -> &SignedBlockHeader {
+        &self.signed
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Handover;
+    use alloy_primitives::U256;
 
     #[test]
     fn test_block_verify() {
@@ -112,10 +171,12 @@ mod tests {
         let header = BlockHeader {
             sequencer: signer.address,
             number: 0,
+            view: 0,
             timestamp: 0,
             parent_digest: None,
             withdrawals_root: "0".to_string(),
             transactions_root: "0".to_string(),
+            handover: None,
         };
         let hash = header.hash();
         assert_eq!(hash, header.hash());
@@ -123,5 +184,43 @@ mod tests {
         let signed = SignedBlockHeader::new(header.clone(), &signer);
         let block = Block::new(signed, vec![]);
         assert!(block.verify());
+
+        // Malformed signatures fail verification rather than panicking.
+        for (r, v) in [(block.signed.signature.r, 7), (U256::ZERO, 0)] {
+            let mut block = block.clone();
+            block.signed.signature.r = r;
+            block.signed.signature.v = v;
+            assert!(!block.verify());
+        }
+    }
+
+    #[test]
+    fn test_block_verify_handover() {
+        let signer = Signer::random();
+        let next = Signer::random();
+        let block = |effective_at: u64, handover_signer: &Signer| {
+            let handover = Handover {
+                current: signer.address,
+                next: next.address,
+                effective_at,
+            };
+            let header = BlockHeader {
+                sequencer: signer.address,
+                number: 1,
+                view: 0,
+                timestamp: 0,
+                parent_digest: None,
+                withdrawals_root: "0".to_string(),
+                transactions_root: "0".to_string(),
+                handover: Some(SignedHandover::new(handover, handover_signer)),
+            };
+            Block::new(SignedBlockHeader::new(header, &signer), vec![])
+        };
+
+        assert!(block(2, &signer).verify());
+        // The handover must take effect after the block recording it.
+        assert!(!block(1, &signer).verify());
+        // The handover must be signed by the sealing sequencer.
+        assert!(!block(2, &next).verify());
     }
 }
@@ -1,50 +1,748 @@
-use crate::{transaction::DynamicTxData, Block, WithdrawalTxData};
+use std::collections::{HashMap, HashSet};
+
+use alloy_primitives::B256;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    check, merkle::Tree, transaction::DynamicTxData, Address, AdmissionError, Block,
+    EquivocationEvidence, MemoryStorage, MerkleProof, Proof, SignedBlockHeader, SignedSoftReceipt,
+    SignedTransaction, State, Storage, StorageError, Transaction, WithdrawalTxData,
+};
+
+/// An error encountered while importing a [Block] into the [Blockchain].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportError {
+    /// The block signature or its recorded handover is invalid.
+    InvalidBlock,
+    /// The block number does not follow the head of the chain.
+    UnexpectedNumber { expected: u64, actual: u64 },
+    /// The parent digest does not match the head of the chain.
+    UnknownParent,
+    /// The block timestamp is not later than the parent block's.
+    InvalidTimestamp,
+    /// The block was sealed by a sequencer other than the scheduled one.
+    WrongSequencer { expected: Address, actual: Address },
+    /// The block records a handover while a previous one has not yet taken effect.
+    PendingHandover,
+    /// The block has not been finalized by the sequencer committee.
+    MissingCertificate,
+    /// The block's commit certificate lacks a quorum of the sequencer committee.
+    InvalidCertificate,
+    /// The block includes a transaction that is invalid in itself.
+    InvalidTransaction { hash: B256, error: AdmissionError },
+    /// The block includes a transaction already included in the chain or earlier in the block.
+    DuplicateTransaction(B256),
+    /// The block includes a transaction that expired before the block.
+    ExpiredTransaction(B256),
+    /// The state roots in the block header do not match applying the block's transactions.
+    InvalidStateRoots,
+    /// The block could not be written to storage.
+    Storage(StorageError),
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::InvalidBlock => write!(f, "invalid block"),
+            ImportError::UnexpectedNumber { expected, actual } => {
+                write!(f, "unexpected block number {actual}, expected {expected}")
+            }
+            ImportError::UnknownParent => write!(f, "unknown parent block"),
+            ImportError::InvalidTimestamp => write!(f, "timestamp not after parent block"),
+            ImportError::WrongSequencer { expected, actual } => {
+                write!(f, "block sealed by {actual}, expected {expected}")
+            }
+            ImportError::PendingHandover => write!(f, "a previous handover is still pending"),
+            ImportError::MissingCertificate => write!(f, "missing commit certificate"),
+            ImportError::InvalidCertificate => write!(f, "invalid commit certificate"),
+            ImportError::InvalidTransaction { hash, error } => {
+                write!(f, "invalid transaction {hash}: {error}")
+            }
+            ImportError::DuplicateTransaction(hash) => write!(f, "duplicate transaction {hash}"),
+            ImportError::ExpiredTransaction(hash) => write!(f, "expired transaction {hash}"),
+            ImportError::InvalidStateRoots => write!(f, "invalid state roots"),
+            ImportError::Storage(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<StorageError> for ImportError {
+    fn from(e: StorageError) -> Self {
+        ImportError::Storage(e)
+    }
+}
+
+/// The status of a block in the [Blockchain].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockStatus {
+    /// The block has been sealed and finalized by the sequencer committee.
+    Sealed,
+    /// The block's execution has been proven by a verified validity proof.
+    Proven,
+}
+
+/// The position of a transaction included in the [Blockchain].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionLocation {
+    /// The number of the block including the transaction.
+    pub number: u64,
+    /// The position of the transaction in the block.
+    pub index: u64,
+}
 
 /// A blockchain containing a list of blocks and an incremental Merkle tree of withdrawals.
+/// Blocks are persisted to a [Storage] backend, in memory unless configured otherwise.
 pub struct Blockchain {
-    /// The chain of blocks in the blockchain.
-    pub(crate) blocks: Vec<Block>,
-    /// The incremental Merkle tree of withdrawals.
-    pub(crate) withdrawals_tree: imt::Tree<sha2::Sha256>,
-    /// The incremental Merkle tree of transactions.
-    pub(crate) transactions_tree: imt::Tree<sha2::Sha256>,
+    /// The storage the blocks and state roots are persisted to.
+    storage: Box<dyn Storage>,
+    /// The head block of the chain, kept at hand for validating the next block.
+    head: Option<Block>,
+    /// The frontiers of both Merkle trees.
+    pub(crate) state: State,
+    /// The tree of the withdrawals applied so far, for proving their inclusion.
+    withdrawals: Tree,
+    /// The verified validity proofs of the blocks proven so far, in block order.
+    pub(crate) proofs: Vec<Proof>,
+    /// The sequencer schedule as pairs of the first block number and the set of sequencers
+    /// taking turns to seal from that block onwards, ordered by block number.
+    pub(crate) sequencers: Vec<(u64, Vec<Address>)>,
+    /// The number of withdrawals applied and the withdrawals root after each block, by block
+    /// number.
+    withdrawal_roots: Vec<(u64, String)>,
+    /// The number of every block, by hash.
+    numbers: HashMap<B256, u64>,
+    /// The location of every included transaction, by hash.
+    locations: HashMap<B256, TransactionLocation>,
+    /// The hashes of the included transactions sent or received by each address, in the
+    /// order they were included.
+    histories: HashMap<Address, Vec<B256>>,
 }
 
-impl Default for Blockchain {
-    fn default() -> Self {
+impl Blockchain {
+    /// Creates an empty blockchain sealed in turns by the given set of sequencers.
+    pub fn new(sequencers: Vec<Address>) -> Self {
+        assert!(!sequencers.is_empty(), "at least one sequencer is required");
         Blockchain {
-            blocks: vec![],
-            withdrawals_tree: imt::Builder::default().build().unwrap(),
-            transactions_tree: imt::Builder::default().build().unwrap(),
+            storage: Box::new(MemoryStorage::default()),
+            head: None,
+            state: State::default(),
+            withdrawals: Tree::default(),
+            proofs: vec![],
+            sequencers: vec![(0, sequencers)],
+            withdrawal_roots: vec![],
+            numbers: HashMap::new(),
+            locations: HashMap::new(),
+            histories: HashMap::new(),
         }
     }
-}
 
-impl Blockchain {
+    /// Persists the chain to the given storage instead of keeping it in memory, resuming
+    /// from any chain already stored there. Every stored block is validated again as it is
+    /// reloaded, rebuilding the Merkle trees and the sequencer schedule, and the roots of the
+    /// rebuilt state must match the stored roots.
+    pub fn with_storage(mut self, storage: impl Storage + 'static) -> Result<Self, ImportError> {
+        for number in 0..storage.height()? {
+            let block = storage
+                .block(number)?
+                .ok_or_else(|| StorageError::Corrupt(format!("missing block {number}")))?;
+            if storage.header(number)?.as_ref() != Some(block.signed()) {
+                let e = format!("header of block {number} does not match its block");
+                return Err(StorageError::Corrupt(e).into());
+            }
+            self.verify(&block)?;
+            self.apply(block);
+        }
+        if storage.roots()? != self.state.roots() {
+            let e = "state roots do not match the stored blocks".to_string();
+            return Err(StorageError::Corrupt(e).into());
+        }
+        self.proofs = storage.proofs()?;
+        if self.proofs.len() as u64 > self.height() {
+            let e = "more proofs stored than blocks".to_string();
+            return Err(StorageError::Corrupt(e).into());
+        }
+        self.storage = Box::new(storage);
+        Ok(self)
+    }
+
     /// Returns the head block of the blockchain.
     pub fn head(&self) -> Option<Block> {
-        self.blocks.last().cloned()
+        self.head.clone()
     }
 
     /// Returns the height of the blockchain.
-    pub(crate) fn height(&self) -> u64 {
-        self.blocks.len() as u64
+    pub fn height(&self) -> u64 {
+        self.head.as_ref().map_or(0, |head| head.number() + 1)
+    }
+
+    /// Returns the block with the given number, if it exists.
+    pub fn block(&self, number: u64) -> Result<Option<Block>, StorageError> {
+        self.storage.block(number)
+    }
+
+    /// Returns the block with the given hash, if it exists.
+    pub fn block_by_hash(&self, hash: &B256) -> Result<Option<Block>, StorageError> {
+        match self.numbers.get(hash) {
+            Some(number) => self.block(*number),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the signed header of the block with the given number, if it exists.
+    pub fn header(&self, number: u64) -> Result<Option<SignedBlockHeader>, StorageError> {
+        self.storage.header(number)
+    }
+
+    /// Returns the status of the block with the given number, if it exists.
+    pub fn status(&self, number: u64) -> Option<BlockStatus> {
+        if number < self.proofs.len() as u64 {
+            Some(BlockStatus::Proven)
+        } else if number < self.height() {
+            Some(BlockStatus::Sealed)
+        } else {
+            None
+        }
+    }
+
+    /// Returns where the transaction with the given hash was included, if it has been.
+    pub fn location(&self, hash: &B256) -> Option<TransactionLocation> {
+        self.locations.get(hash).copied()
+    }
+
+    /// Returns the included transaction with the given hash along with its location.
+    pub fn transaction(
+        &self,
+        hash: &B256,
+    ) -> Result<Option<(SignedTransaction, TransactionLocation)>, StorageError> {
+        let Some(location) = self.location(hash) else {
+            return Ok(None);
+        };
+        let Some(block) = self.block(location.number)? else {
+            return Ok(None);
+        };
+        let transaction = block.transactions.get(location.index as usize).cloned();
+        Ok(transaction.map(|transaction| (transaction, location)))
+    }
+
+    /// Returns the hashes of the included transactions sent or received by the given
+    /// address, in the order they were included.
+    pub fn history(&self, address: &Address) -> &[B256] {
+        self.histories
+            .get(address)
+            .map_or(&[], |hashes| hashes.as_slice())
+    }
+
+    /// Returns the state after applying the given transactions to the state at the
+    /// head of the chain.
+    pub fn execute(&self, transactions: &[SignedTransaction]) -> State {
+        let mut state = self.state.clone();
+        state.apply(transactions);
+        state
+    }
+
+    /// Returns the withdrawals root after the block with the given number, if it exists.
+    pub fn withdrawals_root(&self, number: u64) -> Option<&str> {
+        let (_, root) = self.withdrawal_roots.get(number as usize)?;
+        Some(root)
+    }
+
+    /// Creates a proof of inclusion of the given withdrawal against the withdrawals root
+    /// after the block with the given number, provided the withdrawal was included by then.
+    pub fn withdrawal_proof_at(&self, hash: B256, number: u64) -> Option<MerkleProof> {
+        let (count, _) = self.withdrawal_roots.get(number as usize)?;
+        let index = self.withdrawals.index(&hash)?;
+        self.withdrawals.proof(index, *count)
+    }
+
+    /// Returns the set of sequencers taking turns to seal around the block with the given number.
+    pub fn sequencers(&self, number: u64) -> &[Address] {
+        self.sequencers
+            .iter()
+            .rev()
+            .find(|(from, _)| *from <= number)
+            .map(|(_, sequencers)| sequencers.as_slice())
+            .expect("schedule starts at genesis")
+    }
+
+    /// Returns the address of the sequencer permitted to seal the block with the given number
+    /// in the given view. Sequencers take turns in slots of one block each, and each view
+    /// change passes the slot on to the next sequencer in turn.
+    pub fn sequencer(&self, number: u64, view: u64) -> Address {
+        let sequencers = self.sequencers(number);
+        let len = sequencers.len() as u64;
+        sequencers[((number % len + view % len) % len) as usize]
+    }
+
+    /// Returns evidence of equivocation if the given signed header conflicts with the
+    /// canonical block at the same number sealed by the same sequencer.
+    pub fn equivocation(
+        &self,
+        signed: &SignedBlockHeader,
+    ) -> Result<Option<EquivocationEvidence>, StorageError> {
+        let canonical = self.header(signed.header().number)?;
+        Ok(canonical.and_then(|canonical| EquivocationEvidence::new(canonical, signed.clone())))
+    }
+
+    /// Returns whether the block a soft receipt promises a position in honours it,
+    /// or `None` if the block has not been finalized yet.
+    pub fn honours(&self, receipt: &SignedSoftReceipt) -> Result<Option<bool>, StorageError> {
+        let block = self.block(receipt.receipt().number)?;
+        Ok(block.map(|block| receipt.is_honoured(&block)))
+    }
+
+    /// Validates a finalized block against the head of the chain and pushes it onto the blockchain.
+    pub fn import(&mut self, block: Block) -> Result<(), ImportError> {
+        self.verify(&block)?;
+        self.push(block)?;
+        Ok(())
+    }
+
+    /// Validates a run of finalized blocks following the head of the chain, each against the
+    /// chain as extended by the ones before it, and only then pushes them all onto the
+    /// blockchain, so that an invalid block leaves the chain unchanged.
+    pub fn import_all(&mut self, blocks: Vec<Block>) -> Result<(), ImportError> {
+        let mut scratch = self.scratch();
+        for block in &blocks {
+            scratch.verify(block)?;
+            scratch.apply(block.clone());
+        }
+        for block in blocks {
+            self.push(block)?;
+        }
+        Ok(())
+    }
+
+    /// Returns an in-memory copy of the chain's head, trees and indexes for validating blocks
+    /// ahead of importing them. Its storage is empty, so it must only be extended with `apply`.
+    fn scratch(&self) -> Blockchain {
+        Blockchain {
+            storage: Box::new(MemoryStorage::default()),
+            head: self.head.clone(),
+            state: self.state.clone(),
+            withdrawals: self.withdrawals.clone(),
+            proofs: self.proofs.clone(),
+            sequencers: self.sequencers.clone(),
+            withdrawal_roots: self.withdrawal_roots.clone(),
+            numbers: self.numbers.clone(),
+            locations: self.locations.clone(),
+            histories: self.histories.clone(),
+        }
+    }
+
+    /// Validates a finalized block against the head of the chain, including its commit
+    /// certificate.
+    fn verify(&self, block: &Block) -> Result<(), ImportError> {
+        self.validate(block)?;
+        let certificate = block.certificate().ok_or(ImportError::MissingCertificate)?;
+        if !certificate.verify(block, self.sequencers(block.number())) {
+            return Err(ImportError::InvalidCertificate);
+        }
+        Ok(())
+    }
+
+    /// Validates a proposed block against the head of the chain, without requiring it
+    /// to be finalized.
+    pub fn validate(&self, block: &Block) -> Result<(), ImportError> {
+        if !block.verify() {
+            return Err(ImportError::InvalidBlock);
+        }
+        let header = block.header();
+        if header.number != self.height() {
+            return Err(ImportError::UnexpectedNumber {
+                expected: self.height(),
+                actual: header.number,
+            });
+        }
+        if header.parent_digest != self.head.as_ref().map(|b| b.hash()) {
+            return Err(ImportError::UnknownParent);
+        }
+        if self
+            .head
+            .as_ref()
+            .is_some_and(|parent| header.timestamp <= parent.header().timestamp)
+        {
+            return Err(ImportError::InvalidTimestamp);
+        }
+        let expected = self.sequencer(header.number, header.view);
+        if header.sequencer != expected {
+            return Err(ImportError::WrongSequencer {
+                expected,
+                actual: header.sequencer,
+            });
+        }
+        let pending = self
+            .sequencers
+            .last()
+            .is_some_and(|(from, _)| *from > header.number);
+        if header.handover.is_some() && pending {
+            return Err(ImportError::PendingHandover);
+        }
+        let mut hashes = HashSet::new();
+        for tx in &block.transactions {
+            let hash = tx.transaction.hash();
+            if let Err(error) = check(tx) {
+                return Err(ImportError::InvalidTransaction { hash, error });
+            }
+            if self.locations.contains_key(&hash) || !hashes.insert(hash) {
+                return Err(ImportError::DuplicateTransaction(hash));
+            }
+            if tx.transaction.is_expired(header.number, header.timestamp) {
+                return Err(ImportError::ExpiredTransaction(hash));
+            }
+        }
+        if self.execute(&block.transactions).roots() != header.state_roots() {
+            return Err(ImportError::InvalidStateRoots);
+        }
+        Ok(())
     }
 
-    /// Pushes a block onto the blockchain.
-    pub(crate) fn push(&mut self, block: Block) {
-        self.blocks.push(block);
+    /// Records the verified validity proof of the block following the last proven block,
+    /// persisting it so that the block remains proven across restarts.
+    pub(crate) fn push_proof(&mut self, proof: Proof) -> Result<(), StorageError> {
+        self.storage.commit_proof(&proof)?;
+        self.proofs.push(proof);
+        Ok(())
     }
 
-    /// Appends a withdrawal transaction to the respective incremental Merkle tree.
+    /// Pushes a block onto the blockchain. The block and the resulting state roots are
+    /// committed to storage together before the chain is updated.
+    pub(crate) fn push(&mut self, block: Block) -> Result<(), StorageError> {
+        let roots = self.execute(&block.transactions).roots();
+        self.storage.commit(&block, &roots)?;
+        self.apply(block);
+        Ok(())
+    }
+
+    /// Applies a block's transactions to the Merkle trees and the indexes, and makes it the
+    /// head of the chain. Any handover recorded in the block replaces the handing over
+    /// sequencer in the sequencer schedule.
+    fn apply(&mut self, block: Block) {
+        for (index, tx) in block.transactions.iter().enumerate() {
+            let hash = tx.transaction.hash();
+            let location = TransactionLocation {
+                number: block.number(),
+                index: index as u64,
+            };
+            self.locations.insert(hash, location);
+            let (sender, recipient) = (tx.transaction.sender(), tx.transaction.recipient());
+            self.histories.entry(sender).or_default().push(hash);
+            if recipient != sender {
+                self.histories.entry(recipient).or_default().push(hash);
+            }
+        }
+        for tx in &block.transactions {
+            match &tx.transaction {
+                Transaction::Withdrawal(tx) => self.withdraw(tx),
+                Transaction::Dynamic(tx) => self.transact(tx),
+            }
+        }
+        self.numbers.insert(block.hash(), block.number());
+        self.withdrawal_roots.push((
+            self.withdrawals.len(),
+            block.header().withdrawals_root.clone(),
+        ));
+        if let Some(handover) = &block.header().handover {
+            let handover = handover.handover();
+            let sequencers = self
+                .sequencers(handover.effective_at)
+                .iter()
+                .map(|s| {
+                    if *s == handover.current {
+                        handover.next
+                    } else {
+                        *s
+                    }
+                })
+                .collect();
+            self.sequencers.push((handover.effective_at, sequencers));
+        }
+        self.head = Some(block);
+    }
+
+    /// Appends a withdrawal transaction to the withdrawals Merkle tree.
     pub(crate) fn withdraw(&mut self, tx: &WithdrawalTxData) {
         let hash = tx.hash();
-        self.withdrawals_tree.add_leaf(hash).unwrap();
+        self.state.withdrawals.push(hash);
+        self.withdrawals.push(hash);
     }
 
-    /// Appends a dynamic transaction to the respective incremental Merkle tree.
+    /// Appends a dynamic transaction to the transactions Merkle tree.
     pub(crate) fn transact(&mut self, tx: &DynamicTxData) {
-        let hash = tx.hash();
-        self.transactions_tree.add_leaf(hash).unwrap();
+        self.state.transactions.push(tx.hash());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{certified_block, certify, next_header};
+    use crate::{BlockHeader, Handover, SignedBlockHeader, SignedHandover, Signer};
+
+    /// Creates the next empty block sealed by the given signer.
+    fn block(chain: &Blockchain, signer: &Signer, handover: Option<SignedHandover>) -> Block {
+        transfers(chain, signer, handover, vec![])
+    }
+
+    /// Creates the next block sealed by the given signer, including the given transactions.
+    fn transfers(
+        chain: &Blockchain,
+        signer: &Signer,
+        handover: Option<SignedHandover>,
+        transactions: Vec<SignedTransaction>,
+    ) -> Block {
+        let header = BlockHeader {
+            handover,
+            ..next_header(chain, signer.address, &transactions)
+        };
+        Block::new(SignedBlockHeader::new(header, signer), transactions)
+    }
+
+    #[test]
+    fn test_import_handover() {
+        let current = Signer::random();
+        let next = Signer::random();
+        let mut chain = Blockchain::new(vec![current.address]);
+        let sealed = |chain: &Blockchain, signer: &Signer, handover: Option<SignedHandover>| {
+            certify(block(chain, signer, handover), &[signer])
+        };
+        chain.import(sealed(&chain, &current, None)).unwrap();
+
+        // Record a handover effective from block 3.
+        let handover = Handover {
+            current: current.address,
+            next: next.address,
+            effective_at: 3,
+        };
+        let handover = SignedHandover::new(handover, &current);
+        chain
+            .import(sealed(&chain, &current, Some(handover)))
+            .unwrap();
+
+        // The next sequencer may not seal before the handover takes effect.
+        assert_eq!(
+            chain.import(sealed(&chain, &next, None)),
+            Err(ImportError::WrongSequencer {
+                expected: current.address,
+                actual: next.address,
+            })
+        );
+        chain.import(sealed(&chain, &current, None)).unwrap();
+
+        // From block 3 onwards only the next sequencer may seal.
+        assert_eq!(
+            chain.import(sealed(&chain, &current, None)),
+            Err(ImportError::WrongSequencer {
+                expected: next.address,
+                actual: current.address,
+            })
+        );
+        chain.import(sealed(&chain, &next, None)).unwrap();
+        assert_eq!(chain.height(), 4);
+    }
+
+    #[test]
+    fn test_import_round_robin() {
+        let signers = [Signer::random(), Signer::random(), Signer::random()];
+        let committee: Vec<&Signer> = signers.iter().collect();
+        let mut chain = Blockchain::new(signers.iter().map(|s| s.address).collect());
+        for number in 0..6 {
+            // Only the sequencer in the block's slot may seal it.
+            let slot = number % signers.len();
+            let wrong = &signers[(slot + 1) % signers.len()];
+            assert_eq!(
+                chain.import(certify(block(&chain, wrong, None), &committee)),
+                Err(ImportError::WrongSequencer {
+                    expected: signers[slot].address,
+                    actual: wrong.address,
+                })
+            );
+
+            // The block must be finalized by a quorum of the committee.
+            let proposal = block(&chain, &signers[slot], None);
+            assert_eq!(chain.validate(&proposal), Ok(()));
+            assert_eq!(
+                chain.import(proposal.clone()),
+                Err(ImportError::MissingCertificate)
+            );
+            assert_eq!(
+                chain.import(certify(proposal.clone(), &committee[..2])),
+                Err(ImportError::InvalidCertificate)
+            );
+            chain.import(certify(proposal, &committee)).unwrap();
+        }
+
+        // Each view change passes the slot on to the next sequencer in turn.
+        let next = &signers[1];
+        let header = BlockHeader {
+            view: 1,
+            ..next_header(&chain, next.address, &[])
+        };
+        let proposal = Block::new(SignedBlockHeader::new(header, next), vec![]);
+        assert_eq!(chain.validate(&proposal), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_transactions() {
+        let signer = Signer::random();
+        let mut chain = Blockchain::new(vec![signer.address]);
+
+        // Blocks may only include transactions that would pass admission.
+        let forged = SignedTransaction::new(
+            Transaction::dynamic(signer.address, 1, 0),
+            &Signer::random(),
+        );
+        let empty = SignedTransaction::new(Transaction::dynamic(signer.address, 0, 0), &signer);
+        for transaction in [forged, empty] {
+            let hash = transaction.transaction.hash();
+            let error = check(&transaction).unwrap_err();
+            let block = transfers(&chain, &signer, None, vec![transaction]);
+            assert_eq!(
+                chain.validate(&block),
+                Err(ImportError::InvalidTransaction { hash, error })
+            );
+        }
+
+        // Nor may they include a transaction twice, within a block or across blocks.
+        let transfer = SignedTransaction::new(Transaction::dynamic(signer.address, 1, 0), &signer);
+        let hash = transfer.transaction.hash();
+        let twice = transfers(
+            &chain,
+            &signer,
+            None,
+            vec![transfer.clone(), transfer.clone()],
+        );
+        assert_eq!(
+            chain.validate(&twice),
+            Err(ImportError::DuplicateTransaction(hash))
+        );
+        chain
+            .import(certified_block(&chain, &signer, vec![transfer.clone()]))
+            .unwrap();
+        let again = transfers(&chain, &signer, None, vec![transfer]);
+        assert_eq!(
+            chain.validate(&again),
+            Err(ImportError::DuplicateTransaction(hash))
+        );
+    }
+
+    #[test]
+    fn test_indexes() {
+        let signer = Signer::random();
+        let mut chain = Blockchain::new(vec![signer.address]);
+        let transfer =
+            |nonce| SignedTransaction::new(Transaction::dynamic(signer.address, 1, nonce), &signer);
+        let (first, second) = (transfer(0), transfer(1));
+        let withdrawal =
+            SignedTransaction::new(Transaction::withdrawal(signer.address, 1, 2, 2), &signer);
+        let blocks = [
+            vec![first.clone()],
+            vec![second.clone(), withdrawal.clone()],
+        ];
+        for transactions in blocks {
+            let block = transfers(&chain, &signer, None, transactions);
+            chain.import(certify(block, &[&signer])).unwrap();
+        }
+
+        // Blocks and transactions are looked up by hash.
+        let head = chain.head().unwrap();
+        assert_eq!(chain.block_by_hash(&head.hash()), Ok(Some(head)));
+        assert_eq!(chain.block_by_hash(&B256::random()), Ok(None));
+        let hash = |tx: &SignedTransaction| tx.transaction.hash();
+        let location = TransactionLocation {
+            number: 1,
+            index: 1,
+        };
+        assert_eq!(chain.location(&hash(&withdrawal)), Some(location));
+        assert_eq!(
+            chain.transaction(&hash(&withdrawal)),
+            Ok(Some((withdrawal.clone(), location)))
+        );
+        assert_eq!(chain.location(&B256::random()), None);
+
+        // Senders and recipients have their histories listed in inclusion order, with
+        // transactions to oneself listed once.
+        let sent = vec![hash(&first), hash(&second), hash(&withdrawal)];
+        assert_eq!(chain.history(&signer.address), sent);
+        let recipient = first.transaction.recipient();
+        assert_eq!(chain.history(&recipient), [hash(&first)]);
+        assert!(chain.history(&Address::random()).is_empty());
+    }
+
+    #[test]
+    fn test_historical_withdrawal_proofs() {
+        let signer = Signer::random();
+        let mut chain = Blockchain::new(vec![signer.address]);
+        let withdrawal = |nonce| {
+            SignedTransaction::new(
+                Transaction::withdrawal(signer.address, 1, nonce, 2),
+                &signer,
+            )
+        };
+        let (first, second) = (withdrawal(0), withdrawal(1));
+        for transactions in [vec![first.clone()], vec![second.clone()]] {
+            let block = transfers(&chain, &signer, None, transactions);
+            chain.import(certify(block, &[&signer])).unwrap();
+        }
+        let (old, new) = (
+            chain.withdrawals_root(0).unwrap(),
+            chain.withdrawals_root(1).unwrap(),
+        );
+        assert_eq!(new, chain.head().unwrap().header().withdrawals_root);
+        assert_eq!(chain.withdrawals_root(2), None);
+
+        // A withdrawal can be proven against the root of the block including it and of
+        // every later block.
+        let hash = first.transaction.hash();
+        assert!(chain.withdrawal_proof_at(hash, 0).unwrap().verify(old));
+        assert!(!chain.withdrawal_proof_at(hash, 0).unwrap().verify(new));
+        assert!(chain.withdrawal_proof_at(hash, 1).unwrap().verify(new));
+
+        // Withdrawals cannot be proven against roots from before their inclusion.
+        let hash = second.transaction.hash();
+        assert_eq!(chain.withdrawal_proof_at(hash, 0), None);
+        assert!(chain.withdrawal_proof_at(hash, 1).unwrap().verify(new));
+    }
+
+    #[test]
+    fn test_reload() {
+        let signer = Signer::random();
+        let mut chain = Blockchain::new(vec![signer.address]);
+        let mut blocks = vec![];
+        for _ in 0..3 {
+            let block = certify(block(&chain, &signer, None), &[&signer]);
+            chain.import(block.clone()).unwrap();
+            blocks.push(block);
+        }
+        let stored = |state: &State| {
+            let mut storage = MemoryStorage::default();
+            for block in &blocks {
+                storage.commit(block, &state.roots()).unwrap();
+            }
+            storage
+        };
+
+        // A stored chain is resumed after validating every block.
+        let reloaded = Blockchain::new(vec![signer.address])
+            .with_storage(stored(&chain.state))
+            .unwrap();
+        assert_eq!(reloaded.head(), chain.head());
+        assert_eq!(reloaded.height(), 3);
+
+        // Stored state roots that do not match the blocks are rejected.
+        let mut corrupt = State::default();
+        corrupt.withdrawals.push(B256::random());
+        assert!(matches!(
+            Blockchain::new(vec![signer.address]).with_storage(stored(&corrupt)),
+            Err(ImportError::Storage(StorageError::Corrupt(_)))
+        ));
+
+        // So are stored blocks sealed by sequencers other than the scheduled ones.
+        assert!(matches!(
+            Blockchain::new(vec![Signer::random().address]).with_storage(stored(&chain.state)),
+            Err(ImportError::WrongSequencer { .. })
+        ));
     }
 }
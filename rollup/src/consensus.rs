@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet};
+
+use alloy_primitives::{keccak256, B256};
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, Block, Signature, SignedSubBlock, Signer};
+
+/// Returns the number of votes required for a quorum of a committee of the given size,
+/// i.e. more than two thirds of its members.
+pub fn quorum(committee_size: usize) -> usize {
+    committee_size * 2 / 3 + 1
+}
+
+/// The step of a voting round in which a vote is cast.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum VoteKind {
+    /// A vote that the proposed block is valid.
+    Prevote,
+    /// A vote to commit the proposed block once a quorum has prevoted for it.
+    Precommit,
+}
+
+/// A committee member's vote for a proposed block.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Vote {
+    /// The step of the voting round.
+    pub kind: VoteKind,
+    /// The number of the proposed block.
+    pub number: u64,
+    /// The hash of the proposed block.
+    pub block_hash: B256,
+}
+
+impl Vote {
+    /// Computes the hash of the vote.
+    pub fn hash(&self) -> B256 {
+        let bytes = bincode::serialize(self).unwrap();
+        keccak256(bytes)
+    }
+}
+
+/// A vote signed by a committee member.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct SignedVote {
+    vote: Vote,
+    signature: Signature,
+}
+
+impl SignedVote {
+    /// Creates a new signed vote with the given vote and signer.
+    pub fn new(vote: Vote, signer: &Signer) -> Self {
+        let signature = signer.sign(vote.hash());
+        Self { vote, signature }
+    }
+
+    /// Returns the vote that was signed.
+    pub fn vote(&self) -> &Vote {
+        &self.vote
+    }
+
+    /// Recovers the address of the committee member that cast the vote.
+    pub fn voter(&self) -> Option<Address> {
+        self.signature.recover(self.vote.hash())
+    }
+}
+
+/// A quorum of precommits from the sequencer committee finalizing a block.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct CommitCertificate {
+    precommits: Vec<SignedVote>,
+}
+
+impl CommitCertificate {
+    /// Creates a new commit certificate from the given precommits.
+    pub fn new(precommits: Vec<SignedVote>) -> Self {
+        Self { precommits }
+    }
+
+    /// Verifies that a quorum of distinct committee members precommitted to the given block.
+    pub fn verify(&self, block: &Block, committee: &[Address]) -> bool {
+        let expected = Vote {
+            kind: VoteKind::Precommit,
+            number: block.number(),
+            block_hash: block.hash(),
+        };
+        let voters: HashSet<Address> = self
+            .precommits
+            .iter()
+            .filter(|precommit| precommit.vote == expected)
+            .filter_map(|precommit| precommit.voter())
+            .filter(|voter| committee.contains(voter))
+            .collect();
+        voters.len() >= quorum(committee.len())
+    }
+}
+
+/// The votes of a single kind cast for the blocks proposed at a single number.
+#[derive(Default)]
+pub(crate) struct VoteSet {
+    votes: HashMap<B256, HashMap<Address, SignedVote>>,
+}
+
+impl VoteSet {
+    /// Adds a vote cast by the given voter. Repeated votes from the same voter are ignored.
+    pub(crate) fn add(&mut self, voter: Address, vote: SignedVote) {
+        self.votes
+            .entry(vote.vote.block_hash)
+            .or_default()
+            .entry(voter)
+            .or_insert(vote);
+    }
+
+    /// Returns the number of votes cast for the given block.
+    pub(crate) fn count(&self, block_hash: B256) -> usize {
+        self.votes.get(&block_hash).map_or(0, |votes| votes.len())
+    }
+
+    /// Returns the votes cast for the given block.
+    pub(crate) fn votes(&self, block_hash: B256) -> Vec<SignedVote> {
+        self.votes
+            .get(&block_hash)
+            .map(|votes| votes.values().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// The state of the voting round for a single block number.
+#[derive(Default)]
+pub(crate) struct Round {
+    /// The view this node is in, selecting the sequencer expected to propose.
+    pub(crate) view: u64,
+    /// The number of consecutive block periods a block has been due in the current view
+    /// without the chain moving on.
+    pub(crate) stalls: u32,
+    /// The block proposed in this round, in the current view or a later one.
+    pub(crate) proposal: Option<Block>,
+    /// The latest preconfirmation the proposal must be consistent with.
+    pub(crate) preconfirmation: Option<SignedSubBlock>,
+    /// The prevotes cast in this round.
+    pub(crate) prevotes: VoteSet,
+    /// The precommits cast in this round.
+    pub(crate) precommits: VoteSet,
+    /// Whether this node has prevoted in this round.
+    pub(crate) prevoted: bool,
+    /// Whether this node has precommitted in this round.
+    pub(crate) precommitted: bool,
+    /// The messages this node gossiped in this round, kept for re-gossiping.
+    pub(crate) sent: Vec<(Vec<u8>, String)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlockHeader, SignedBlockHeader};
+
+    #[test]
+    fn test_commit_certificate() {
+        let signers: Vec<Signer> = (0..4).map(|_| Signer::random()).collect();
+        let committee: Vec<Address> = signers.iter().map(|s| s.address).collect();
+        let header = BlockHeader {
+            sequencer: signers[0].address,
+            number: 0,
+            view: 0,
+            timestamp: 0,
+            parent_digest: None,
+            withdrawals_root: "0".to_string(),
+            transactions_root: "0".to_string(),
+            handover: None,
+        };
+        let block = Block::new(SignedBlockHeader::new(header, &signers[0]), vec![]);
+        let precommit = |signer: &Signer| {
+            let vote = Vote {
+                kind: VoteKind::Precommit,
+                number: 0,
+                block_hash: block.hash(),
+            };
+            SignedVote::new(vote, signer)
+        };
+
+        // Three of four committee members form a quorum.
+        let certificate = CommitCertificate::new(signers[..3].iter().map(precommit).collect());
+        assert!(certificate.verify(&block, &committee));
+
+        // Two of four committee members do not, even when repeated.
+        let mut precommits: Vec<SignedVote> = signers[..2].iter().map(precommit).collect();
+        precommits.push(precommit(&signers[0]));
+        assert!(!CommitCertificate::new(precommits.clone()).verify(&block, &committee));
+
+        // Votes from outside the committee are not counted.
+        precommits.push(precommit(&Signer::random()));
+        assert!(!CommitCertificate::new(precommits).verify(&block, &committee));
+    }
+}
@@ -0,0 +1,184 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, SignedBlockHeader};
+
+/// Self-contained proof that a sequencer signed two different block headers at the same number.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct EquivocationEvidence {
+    first: SignedBlockHeader,
+    second: SignedBlockHeader,
+}
+
+impl EquivocationEvidence {
+    /// Creates evidence from two signed headers if they prove an equivocation.
+    /// The headers are ordered by hash so that the same equivocation always yields the same evidence.
+    pub fn new(a: SignedBlockHeader, b: SignedBlockHeader) -> Option<Self> {
+        let (first, second) = if a.header().hash() <= b.header().hash() {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let evidence = Self { first, second };
+        evidence.verify().then_some(evidence)
+    }
+
+    /// Verifies that both headers are validly signed by the same sequencer
+    /// for the same block number in the same view, yet differ.
+    pub fn verify(&self) -> bool {
+        let (first, second) = (self.first.header(), self.second.header());
+        first.number == second.number
+            && first.view == second.view
+            && first.sequencer == second.sequencer
+            && first.hash() != second.hash()
+            && self.first.verify()
+            && self.second.verify()
+    }
+
+    /// Returns the address of the equivocating sequencer.
+    pub fn offender(&self) -> Address {
+        self.first.header().sequencer
+    }
+
+    /// Returns the block number at which the sequencer equivocated.
+    pub fn number(&self) -> u64 {
+        self.first.header().number
+    }
+}
+
+/// A store of verified equivocation evidence, optionally persisted to a file of JSON lines.
+#[derive(Default)]
+pub struct EvidenceStore {
+    /// The evidence collected so far.
+    evidence: Vec<EquivocationEvidence>,
+    /// The file the evidence is appended to.
+    path: Option<PathBuf>,
+}
+
+impl EvidenceStore {
+    /// Opens the store persisted at the given path, loading any evidence recorded there.
+    /// Lines that cannot be parsed, such as one torn by a crash mid-write, are skipped and
+    /// dropped from the file so that later evidence is not appended onto them.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let mut evidence = vec![];
+        let mut skipped = false;
+        if path.exists() {
+            for line in BufReader::new(File::open(&path)?).split(b'\n') {
+                match serde_json::from_slice::<EquivocationEvidence>(&line?) {
+                    Ok(item) if item.verify() => evidence.push(item),
+                    Ok(_) => {
+                        warn!("Skipped invalid evidence");
+                        skipped = true;
+                    }
+                    Err(e) => {
+                        warn!("Skipped unreadable evidence: {}", e);
+                        skipped = true;
+                    }
+                }
+            }
+        }
+        let store = Self {
+            evidence,
+            path: Some(path),
+        };
+        if skipped {
+            store.rewrite()?;
+        }
+        Ok(store)
+    }
+
+    /// Replaces the contents of the file with the evidence in the store.
+    fn rewrite(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        // Write to a temporary file first so that a crash never leaves partial evidence.
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        for evidence in &self.evidence {
+            writeln!(file, "{}", serde_json::to_string(evidence)?)?;
+        }
+        file.sync_all()?;
+        fs::rename(tmp, path)
+    }
+
+    /// Adds evidence to the store unless it is invalid or already known.
+    /// Returns whether the evidence was added.
+    pub fn add(&mut self, evidence: EquivocationEvidence) -> io::Result<bool> {
+        if !evidence.verify() || self.evidence.contains(&evidence) {
+            return Ok(false);
+        }
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(&evidence)?)?;
+        }
+        self.evidence.push(evidence);
+        Ok(true)
+    }
+
+    /// Returns all evidence in the store.
+    pub fn all(&self) -> &[EquivocationEvidence] {
+        &self.evidence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlockHeader, Signer};
+
+    fn header(signer: &Signer, timestamp: u64) -> SignedBlockHeader {
+        let header = BlockHeader {
+            sequencer: signer.address,
+            number: 7,
+            view: 0,
+            timestamp,
+            parent_digest: None,
+            withdrawals_root: "0".to_string(),
+            transactions_root: "0".to_string(),
+            handover: None,
+        };
+        SignedBlockHeader::new(header, signer)
+    }
+
+    #[test]
+    fn test_equivocation_evidence() {
+        let signer = Signer::random();
+        let evidence = EquivocationEvidence::new(header(&signer, 1), header(&signer, 2)).unwrap();
+        assert_eq!(evidence.offender(), signer.address);
+        assert_eq!(evidence.number(), 7);
+
+        // The same equivocation yields the same evidence regardless of order.
+        let reversed = EquivocationEvidence::new(header(&signer, 2), header(&signer, 1));
+        assert_eq!(reversed, Some(evidence.clone()));
+
+        // Identical headers or headers from different sequencers are not evidence.
+        assert!(EquivocationEvidence::new(header(&signer, 1), header(&signer, 1)).is_none());
+        let other = Signer::random();
+        assert!(EquivocationEvidence::new(header(&signer, 1), header(&other, 2)).is_none());
+
+        // Evidence is persisted across reopening the store, without duplicates.
+        let path = std::env::temp_dir().join(format!("evidence-{}.jsonl", signer.address));
+        let mut store = EvidenceStore::open(&path).unwrap();
+        assert!(store.add(evidence.clone()).unwrap());
+        assert!(!store.add(evidence.clone()).unwrap());
+        let store = EvidenceStore::open(&path).unwrap();
+        assert_eq!(store.all(), std::slice::from_ref(&evidence));
+
+        // A line torn by a crash mid-write is skipped, keeping the evidence around it.
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"first\":").unwrap();
+        let mut store = EvidenceStore::open(&path).unwrap();
+        assert_eq!(store.all(), std::slice::from_ref(&evidence));
+        let later = EquivocationEvidence::new(header(&signer, 3), header(&signer, 4)).unwrap();
+        assert!(store.add(later.clone()).unwrap());
+        let store = EvidenceStore::open(&path).unwrap();
+        assert_eq!(store.all(), &[evidence, later]);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+use alloy_primitives::{keccak256, B256};
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, Signature, Signer};
+
+/// A handover of sequencing rights from the current sequencer to a new one.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Handover {
+    /// The address of the sequencer handing over.
+    pub current: Address,
+    /// The address of the sequencer taking over.
+    pub next: Address,
+    /// The number of the first block to be sealed by the next sequencer.
+    pub effective_at: u64,
+}
+
+impl Handover {
+    /// Computes the hash of the handover.
+    pub fn hash(&self) -> B256 {
+        let bytes = bincode::serialize(self).unwrap();
+        keccak256(bytes)
+    }
+}
+
+/// A handover signed by the sequencer handing over.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct SignedHandover {
+    handover: Handover,
+    signature: Signature,
+}
+
+impl SignedHandover {
+    /// Creates a new signed handover with the given handover and signer.
+    pub fn new(handover: Handover, signer: &Signer) -> Self {
+        let signature = signer.sign(handover.hash());
+        Self {
+            handover,
+            signature,
+        }
+    }
+
+    /// Returns the handover that was signed.
+    pub fn handover(&self) -> &Handover {
+        &self.handover
+    }
+
+    /// Verifies the signature of the [SignedHandover] is valid and that it matches
+    /// the address of the current sequencer specified in the [Handover].
+    pub fn verify(&self) -> bool {
+        self.signature.recover(self.handover.hash()) == Some(self.handover.current)
+    }
+}
+
+/// An error requesting a handover from a [KeyRotator](crate::KeyRotator).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandoverError {
+    /// The handover is not signed by the sequencer handing over.
+    InvalidSignature,
+    /// The sequencer holds no key for the address taking over.
+    UnknownKey(Address),
+}
+
+impl std::fmt::Display for HandoverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandoverError::InvalidSignature => {
+                write!(f, "handover is not signed by the current sequencer")
+            }
+            HandoverError::UnknownKey(address) => write!(f, "no key held for {address}"),
+        }
+    }
+}
+
+impl std::error::Error for HandoverError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handover_verify() {
+        let current = Signer::random();
+        let next = Signer::random();
+        let handover = Handover {
+            current: current.address,
+            next: next.address,
+            effective_at: 10,
+        };
+        assert!(SignedHandover::new(handover.clone(), &current).verify());
+        assert!(!SignedHandover::new(handover, &next).verify());
+    }
+}
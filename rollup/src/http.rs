@@ -0,0 +1,248 @@
+use std::sync::Arc;
+
+use alloy_primitives::B256;
+use log::error;
+use rocket::http::Status;
+use rocket::response::status::Custom;
+use rocket::response::stream::{Event, EventStream};
+use rocket::tokio::select;
+use rocket::{get, routes, Route, Shutdown, State};
+use serde_json::{json, Value};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::Mutex;
+
+use crate::{
+    Address, AdmissionError, Blockchain, EvidenceStore, MempoolError, SignedSubBlock, StorageError,
+};
+
+/// The number of items in a page of results unless requested otherwise.
+pub const PAGE_SIZE: usize = 100;
+/// The maximum number of items in a page of results.
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// Returns the HTTP endpoints served by every node over the chain it follows, mounted by the
+/// sequencer and the RPC node alike. They expect the node to manage the
+/// `Arc<Mutex<Blockchain>>` it follows, its `Arc<Mutex<EvidenceStore>>` and the
+/// `broadcast::Sender<SignedSubBlock>` of preconfirmations it streams.
+pub fn routes() -> Vec<Route> {
+    routes![
+        head,
+        evidence,
+        preconfirmations,
+        block,
+        block_by_hash,
+        blocks,
+        header,
+        transaction,
+        transaction_location,
+        address_transactions,
+        withdrawal_proof,
+        withdrawals_root
+    ]
+}
+
+/// Responds with the reason a transaction was not admitted, identified by a machine-readable
+/// code.
+pub fn rejection(tx_digest: String, e: AdmissionError) -> Custom<Value> {
+    let status = if e.is_conflict() {
+        Status::Conflict
+    } else if matches!(
+        e,
+        AdmissionError::Mempool(MempoolError::Full) | AdmissionError::Unavailable(_)
+    ) {
+        Status::ServiceUnavailable
+    } else {
+        Status::BadRequest
+    };
+    let error = json!({ "code": e.code(), "message": e.to_string() });
+    Custom(status, json!({ "tx_digest": tx_digest, "error": error }))
+}
+
+/// Logs a failure to read the chain from storage and responds with an internal server error.
+fn internal(e: StorageError) -> Status {
+    error!("Failed to read from storage: {}", e);
+    Status::InternalServerError
+}
+
+/// Returns the head block of the blockchain.
+#[get("/")]
+async fn head(chain: &State<Arc<Mutex<Blockchain>>>) -> Value {
+    let head = chain.lock().await.head();
+    json!(head)
+}
+
+/// Returns the evidence of equivocating sequencers collected by this node.
+#[get("/evidence")]
+async fn evidence(store: &State<Arc<Mutex<EvidenceStore>>>) -> Value {
+    json!(store.lock().await.all())
+}
+
+/// Streams the preconfirmations of upcoming blocks as server-sent events.
+#[get("/preconfirmations")]
+fn preconfirmations(
+    preconfirmations: &State<broadcast::Sender<SignedSubBlock>>,
+    mut shutdown: Shutdown,
+) -> EventStream![] {
+    let mut rx = preconfirmations.subscribe();
+    EventStream! {
+        loop {
+            let preconfirmation = select! {
+                msg = rx.recv() => match msg {
+                    Ok(preconfirmation) => preconfirmation,
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(_)) => continue,
+                },
+                _ = &mut shutdown => break,
+            };
+            yield Event::json(&preconfirmation);
+        }
+    }
+}
+
+/// Returns the block with the given number.
+#[get("/blocks/<number>")]
+async fn block(
+    chain: &State<Arc<Mutex<Blockchain>>>,
+    number: u64,
+) -> Result<Option<Value>, Status> {
+    let block = chain.lock().await.block(number).map_err(internal)?;
+    Ok(block.map(|block| json!(block)))
+}
+
+/// Returns the block with the given hash.
+#[get("/blocks/hash/<hash>", rank = 1)]
+async fn block_by_hash(
+    chain: &State<Arc<Mutex<Blockchain>>>,
+    hash: &str,
+) -> Result<Option<Value>, Status> {
+    let Ok(hash) = hash.parse::<B256>() else {
+        return Ok(None);
+    };
+    let block = chain.lock().await.block_by_hash(&hash).map_err(internal)?;
+    Ok(block.map(|block| json!(block)))
+}
+
+/// Returns a page of the blocks numbered `from` to `to` inclusive, along with the number of
+/// the block starting the next page if there are more.
+#[get("/blocks?<from>&<to>")]
+async fn blocks(
+    chain: &State<Arc<Mutex<Blockchain>>>,
+    from: Option<u64>,
+    to: Option<u64>,
+) -> Result<Value, Status> {
+    let from = from.unwrap_or(0);
+    let last = from.saturating_add(MAX_PAGE_SIZE as u64 - 1);
+    let to = to
+        .unwrap_or(from.saturating_add(PAGE_SIZE as u64 - 1))
+        .min(last);
+    let chain = chain.lock().await;
+    let mut blocks = vec![];
+    for number in from..=to {
+        let Some(block) = chain.block(number).map_err(internal)? else {
+            break;
+        };
+        blocks.push(block);
+    }
+    let next = blocks
+        .last()
+        .map(|block| block.number() + 1)
+        .filter(|next| *next < chain.height());
+    Ok(json!({ "blocks": blocks, "next": next }))
+}
+
+/// Returns the signed header of the block with the given number.
+#[get("/headers/<number>")]
+async fn header(
+    chain: &State<Arc<Mutex<Blockchain>>>,
+    number: u64,
+) -> Result<Option<Value>, Status> {
+    let header = chain.lock().await.header(number).map_err(internal)?;
+    Ok(header.map(|header| json!(header)))
+}
+
+/// Returns the included transaction with the given hash, along with the number of the block
+/// including it and its position in the block.
+#[get("/transactions/<hash>")]
+async fn transaction(
+    chain: &State<Arc<Mutex<Blockchain>>>,
+    hash: &str,
+) -> Result<Option<Value>, Status> {
+    let Ok(hash) = hash.parse::<B256>() else {
+        return Ok(None);
+    };
+    let transaction = chain.lock().await.transaction(&hash).map_err(internal)?;
+    Ok(transaction.map(|(transaction, location)| {
+        json!({
+            "transaction": transaction,
+            "number": location.number,
+            "index": location.index,
+        })
+    }))
+}
+
+/// Returns the number of the block including the transaction with the given hash and its
+/// position in the block.
+#[get("/transactions/<hash>/location")]
+async fn transaction_location(chain: &State<Arc<Mutex<Blockchain>>>, hash: &str) -> Option<Value> {
+    let hash: B256 = hash.parse().ok()?;
+    let location = chain.lock().await.location(&hash)?;
+    Some(json!({ "hash": hash, "number": location.number, "index": location.index }))
+}
+
+/// Returns a page of the transactions sent or received by the given address, in the order
+/// they were included, starting at the given offset.
+#[get("/addresses/<address>/transactions?<offset>&<limit>")]
+async fn address_transactions(
+    chain: &State<Arc<Mutex<Blockchain>>>,
+    address: &str,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Option<Value> {
+    let address: Address = address.parse().ok()?;
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(PAGE_SIZE).min(MAX_PAGE_SIZE);
+    let chain = chain.lock().await;
+    let history = chain.history(&address);
+    let transactions: Vec<Value> = history
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .filter_map(|hash| {
+            let location = chain.location(hash)?;
+            Some(json!({ "hash": hash, "number": location.number, "index": location.index }))
+        })
+        .collect();
+    Some(json!({
+        "address": address,
+        "total": history.len(),
+        "offset": offset,
+        "transactions": transactions,
+    }))
+}
+
+/// Returns a proof of inclusion of the withdrawal with the given hash against the
+/// withdrawals root after the block with the given number, or the head block if unset.
+#[get("/withdrawals/<hash>/proof?<number>")]
+async fn withdrawal_proof(
+    chain: &State<Arc<Mutex<Blockchain>>>,
+    hash: &str,
+    number: Option<u64>,
+) -> Option<Value> {
+    let hash: B256 = hash.parse().ok()?;
+    let chain = chain.lock().await;
+    let number = match number {
+        Some(number) => number,
+        None => chain.head()?.number(),
+    };
+    let proof = chain.withdrawal_proof_at(hash, number)?;
+    Some(json!({ "number": number, "proof": proof }))
+}
+
+/// Returns the withdrawals root after the block with the given number, against which
+/// withdrawals included by then can be proven.
+#[get("/blocks/<number>/withdrawals")]
+async fn withdrawals_root(chain: &State<Arc<Mutex<Blockchain>>>, number: u64) -> Option<Value> {
+    let chain = chain.lock().await;
+    let root = chain.withdrawals_root(number)?;
+    Some(json!({ "number": number, "withdrawals_root": root }))
+}
@@ -0,0 +1,358 @@
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use log::{info, warn};
+use tokio::sync::Mutex;
+
+use crate::journal::Journal;
+use crate::{
+    Address, Blockchain, Clock, Mempool, MempoolError, SignedTransaction, SystemClock, CHAIN_ID,
+};
+
+/// An error encountered while admitting a transaction through the [Ingress].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdmissionError {
+    /// The transaction is intended for another chain.
+    WrongChain { expected: u64, actual: u64 },
+    /// The transaction's signature is malformed.
+    InvalidSignature,
+    /// The transaction was signed by someone other than its sender.
+    WrongSender { sender: Address, signer: Address },
+    /// The transaction transfers no value.
+    ZeroAmount,
+    /// The transaction expired before the upcoming block.
+    Expired,
+    /// The transaction has already been included in the chain.
+    AlreadyIncluded,
+    /// A transaction from the sender with the same nonce has already been preconfirmed and
+    /// can no longer be replaced.
+    Preconfirmed(u64),
+    /// The mempool did not accept the transaction.
+    Mempool(MempoolError),
+    /// The node failed to take the transaction in, e.g. to journal it, and the transaction
+    /// may be submitted again later.
+    Unavailable(String),
+}
+
+impl std::fmt::Display for AdmissionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdmissionError::WrongChain { expected, actual } => {
+                write!(f, "wrong chain id {actual}, expected {expected}")
+            }
+            AdmissionError::InvalidSignature => write!(f, "invalid signature"),
+            AdmissionError::WrongSender { sender, signer } => {
+                write!(f, "transaction from {sender} signed by {signer}")
+            }
+            AdmissionError::ZeroAmount => write!(f, "zero amount"),
+            AdmissionError::Expired => write!(f, "transaction expired"),
+            AdmissionError::AlreadyIncluded => write!(f, "transaction already included"),
+            AdmissionError::Preconfirmed(nonce) => {
+                write!(
+                    f,
+                    "a transaction with nonce {nonce} is already preconfirmed"
+                )
+            }
+            AdmissionError::Mempool(e) => write!(f, "{e}"),
+            AdmissionError::Unavailable(e) => write!(f, "unavailable: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for AdmissionError {}
+
+impl AdmissionError {
+    /// Returns a stable, machine-readable code identifying the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AdmissionError::WrongChain { .. } => "wrong_chain",
+            AdmissionError::InvalidSignature => "invalid_signature",
+            AdmissionError::WrongSender { .. } => "wrong_sender",
+            AdmissionError::ZeroAmount => "zero_amount",
+            AdmissionError::Expired => "expired",
+            AdmissionError::AlreadyIncluded => "already_included",
+            AdmissionError::Preconfirmed(_) => "preconfirmed",
+            AdmissionError::Mempool(MempoolError::AlreadyKnown) => "already_known",
+            AdmissionError::Mempool(MempoolError::NonceTooLow { .. }) => "nonce_too_low",
+            AdmissionError::Mempool(MempoolError::ReplacementUnderpriced) => {
+                "replacement_underpriced"
+            }
+            AdmissionError::Mempool(MempoolError::Full) => "mempool_full",
+            AdmissionError::Unavailable(_) => "unavailable",
+        }
+    }
+
+    /// Returns whether the transaction is valid but conflicts with one already known, as
+    /// opposed to being invalid in itself or turned away for lack of room.
+    pub fn is_conflict(&self) -> bool {
+        matches!(
+            self,
+            AdmissionError::AlreadyIncluded
+                | AdmissionError::Preconfirmed(_)
+                | AdmissionError::Mempool(
+                    MempoolError::AlreadyKnown
+                        | MempoolError::NonceTooLow { .. }
+                        | MempoolError::ReplacementUnderpriced
+                )
+        )
+    }
+}
+
+impl From<MempoolError> for AdmissionError {
+    fn from(e: MempoolError) -> Self {
+        AdmissionError::Mempool(e)
+    }
+}
+
+/// Checks the properties of a transaction that do not depend on the chain: it must be
+/// intended for this chain, signed by its sender and transfer some value.
+pub fn check(transaction: &SignedTransaction) -> Result<(), AdmissionError> {
+    let tx = &transaction.transaction;
+    if tx.chain_id() != CHAIN_ID {
+        return Err(AdmissionError::WrongChain {
+            expected: CHAIN_ID,
+            actual: tx.chain_id(),
+        });
+    }
+    let signer = transaction
+        .signer()
+        .ok_or(AdmissionError::InvalidSignature)?;
+    if signer != tx.sender() {
+        return Err(AdmissionError::WrongSender {
+            sender: tx.sender(),
+            signer,
+        });
+    }
+    if tx.amount() == 0 {
+        return Err(AdmissionError::ZeroAmount);
+    }
+    Ok(())
+}
+
+/// The single path through which transactions enter a node's [Mempool], whether submitted
+/// over HTTP, gossiped by other nodes or added directly to a sequencer.
+/// Transactions are validated against the chain before they are pooled, and optionally
+/// journaled to disk so that they survive a restart.
+#[derive(Clone)]
+pub struct Ingress {
+    /// The mempool admitted transactions are added to.
+    mempool: Arc<Mutex<Mempool>>,
+    /// The blockchain transactions are validated against.
+    blockchain: Arc<Mutex<Blockchain>>,
+    /// The clock used to check whether transactions have expired.
+    clock: Arc<dyn Clock>,
+    /// The journal admitted transactions are recorded in, if any.
+    journal: Arc<Mutex<Option<Journal>>>,
+}
+
+impl Ingress {
+    /// Creates a new [Ingress] admitting transactions to the given mempool.
+    pub fn new(mempool: Arc<Mutex<Mempool>>, blockchain: Arc<Mutex<Blockchain>>) -> Self {
+        Self {
+            mempool,
+            blockchain,
+            clock: Arc::new(SystemClock),
+            journal: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Uses the given clock to check expiry instead of the system clock.
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            ..self
+        }
+    }
+
+    /// Journals admitted transactions to the file at the given path. The transactions recorded
+    /// there by a previous run are first replayed through admission, dropping those that are
+    /// no longer valid. Returns the number of transactions restored.
+    pub async fn open_journal(&self, path: impl Into<PathBuf>) -> io::Result<usize> {
+        let (mut journal, transactions) = Journal::open(path)?;
+        let mut restored = 0;
+        for transaction in transactions {
+            let hash = transaction.transaction.hash();
+            match self.admit(transaction).await {
+                Ok(()) => restored += 1,
+                Err(e) => info!("Dropped journaled transaction {}: {}", hash, e),
+            }
+        }
+        let mut current = self.journal.lock().await;
+        journal.rewrite(&self.mempool.lock().await.transactions())?;
+        *current = Some(journal);
+        Ok(restored)
+    }
+
+    /// Rewrites the journal, if any, to hold only the transactions still in the mempool.
+    pub async fn compact(&self) -> io::Result<()> {
+        let mut journal = self.journal.lock().await;
+        match journal.as_mut() {
+            Some(journal) => journal.rewrite(&self.mempool.lock().await.transactions()),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the mempool admitted transactions are added to.
+    pub fn mempool(&self) -> &Arc<Mutex<Mempool>> {
+        &self.mempool
+    }
+
+    /// Validates a transaction and adds it to the mempool. The transaction must pass [check],
+    /// not have expired before the upcoming block and not duplicate a known or included
+    /// transaction.
+    pub async fn admit(&self, transaction: SignedTransaction) -> Result<(), AdmissionError> {
+        check(&transaction)?;
+        let chain = self.blockchain.lock().await;
+        if chain.location(&transaction.transaction.hash()).is_some() {
+            return Err(AdmissionError::AlreadyIncluded);
+        }
+        if transaction
+            .transaction
+            .is_expired(chain.height(), self.clock.now())
+        {
+            return Err(AdmissionError::Expired);
+        }
+        drop(chain);
+
+        // Journal the transaction before adding it, so that no admitted transaction is lost on
+        // a restart, and hold the journal meanwhile so that it is not compacted away. Entries
+        // of transactions the mempool turns away are dropped when the journal is replayed.
+        let mut journal = self.journal.lock().await;
+        if let Some(journal) = journal.as_mut() {
+            if let Err(e) = journal.append(&transaction) {
+                warn!("Failed to journal transaction: {}", e);
+                return Err(AdmissionError::Unavailable(e.to_string()));
+            }
+        }
+        self.mempool.lock().await.add(transaction)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::certified_block;
+    use crate::{Expiry, ManualClock, Signer, Transaction};
+
+    #[tokio::test]
+    async fn test_admission() {
+        let signer = Signer::random();
+        let chain = Arc::new(Mutex::new(Blockchain::new(vec![signer.address])));
+        let ingress = Ingress::new(Arc::new(Mutex::new(Mempool::default())), chain.clone());
+        let transaction =
+            SignedTransaction::new(Transaction::dynamic(signer.address, 1, 0), &signer);
+
+        // Transactions for another chain are rejected.
+        let mut json = serde_json::to_value(&transaction).unwrap();
+        json["transaction"]["Dynamic"]["header"]["chain_id"] = 1.into();
+        let other_chain: SignedTransaction = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            ingress.admit(other_chain).await,
+            Err(AdmissionError::WrongChain {
+                expected: CHAIN_ID,
+                actual: 1
+            })
+        );
+
+        // Transactions must be signed by their sender.
+        let mut malformed = transaction.clone();
+        malformed.signature.v = 7;
+        assert_eq!(
+            ingress.admit(malformed).await,
+            Err(AdmissionError::InvalidSignature)
+        );
+        let impostor = Signer::random();
+        let forged = SignedTransaction::new(transaction.transaction.clone(), &impostor);
+        let error = ingress.admit(forged).await.unwrap_err();
+        assert_eq!(error.code(), "wrong_sender");
+        assert!(!error.is_conflict());
+
+        // Transactions must transfer some value.
+        let empty = SignedTransaction::new(Transaction::dynamic(signer.address, 0, 0), &signer);
+        assert_eq!(ingress.admit(empty).await, Err(AdmissionError::ZeroAmount));
+
+        // Valid transactions are admitted once.
+        ingress.admit(transaction.clone()).await.unwrap();
+        let error = ingress.admit(transaction.clone()).await.unwrap_err();
+        assert_eq!(error.code(), "already_known");
+        assert!(error.is_conflict());
+
+        // Included transactions are rejected, even by a mempool that has not seen them.
+        let block = certified_block(&*chain.lock().await, &signer, vec![transaction.clone()]);
+        chain.lock().await.import(block).unwrap();
+        let restarted = Ingress::new(Arc::new(Mutex::new(Mempool::default())), chain);
+        assert_eq!(
+            restarted.admit(transaction).await,
+            Err(AdmissionError::AlreadyIncluded)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_journal_replay() {
+        let signer = Signer::random();
+        let path = std::env::temp_dir().join(format!("journal-{}.jsonl", signer.address));
+        let clock = ManualClock::new(100);
+        let ingress = || {
+            let chain = Arc::new(Mutex::new(Blockchain::new(vec![signer.address])));
+            let mempool = Arc::new(Mutex::new(Mempool::default()));
+            Ingress::new(mempool, chain).with_clock(clock.clone())
+        };
+        let transaction = |nonce, expiry| {
+            let tx = Transaction::dynamic(signer.address, 1, nonce).with_expiry(expiry);
+            SignedTransaction::new(tx, &signer)
+        };
+        let sealed = transaction(0, Expiry::Block(10));
+        let lasting = transaction(1, Expiry::Block(10));
+        let expiring = transaction(2, Expiry::Time(150));
+
+        // Admitted transactions are journaled, and sealed ones are compacted away.
+        let first = ingress();
+        assert_eq!(first.open_journal(&path).await.unwrap(), 0);
+        for tx in [&sealed, &lasting, &expiring] {
+            first.admit(tx.clone()).await.unwrap();
+        }
+        first
+            .mempool()
+            .lock()
+            .await
+            .remove_included(std::slice::from_ref(&sealed));
+        first.compact().await.unwrap();
+
+        // A line torn by a crash mid-write is skipped.
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        std::io::Write::write_all(&mut file, b"{\"transaction\":").unwrap();
+
+        // After a restart, the journal is replayed with revalidation.
+        clock.advance(100);
+        let second = ingress();
+        assert_eq!(second.open_journal(&path).await.unwrap(), 1);
+        assert_eq!(second.mempool().lock().await.transactions(), vec![lasting]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_journal_failure() {
+        let signer = Signer::random();
+        let path = std::env::temp_dir().join(format!("journal-{}.jsonl", signer.address));
+        let chain = Arc::new(Mutex::new(Blockchain::new(vec![signer.address])));
+        let ingress = Ingress::new(Arc::new(Mutex::new(Mempool::default())), chain);
+        ingress.open_journal(&path).await.unwrap();
+
+        // A transaction that cannot be journaled is not admitted.
+        std::fs::remove_file(&path).unwrap();
+        std::fs::create_dir(&path).unwrap();
+        let tx = Transaction::dynamic(signer.address, 1, 0);
+        let error = ingress
+            .admit(SignedTransaction::new(tx, &signer))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), "unavailable");
+        assert!(ingress.mempool().lock().await.is_empty());
+        std::fs::remove_dir(path).unwrap();
+    }
+}
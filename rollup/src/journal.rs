@@ -0,0 +1,56 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use log::warn;
+
+use crate::SignedTransaction;
+
+/// A journal of the transactions admitted to a mempool but not yet sealed, persisted to a file
+/// of JSON lines so that they survive a restart.
+pub(crate) struct Journal {
+    /// The file the transactions are appended to.
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Opens the journal at the given path, returning the transactions recorded there in the
+    /// order they were admitted. Lines that cannot be parsed, such as one torn by a crash
+    /// mid-write, are skipped.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<(Self, Vec<SignedTransaction>)> {
+        let path = path.into();
+        let mut transactions = vec![];
+        if path.exists() {
+            for line in BufReader::new(File::open(&path)?).split(b'\n') {
+                match serde_json::from_slice(&line?) {
+                    Ok(transaction) => transactions.push(transaction),
+                    Err(e) => warn!("Skipped unreadable journal entry: {}", e),
+                }
+            }
+        }
+        Ok((Self { path }, transactions))
+    }
+
+    /// Appends an admitted transaction to the journal, syncing it to disk before returning.
+    pub fn append(&mut self, transaction: &SignedTransaction) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(transaction)?)?;
+        file.sync_data()
+    }
+
+    /// Replaces the contents of the journal with the given transactions, dropping those that
+    /// have since been sealed or evicted.
+    pub fn rewrite(&mut self, transactions: &[SignedTransaction]) -> io::Result<()> {
+        // Write to a temporary file first so that a crash never leaves a partial journal.
+        let tmp = self.path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        for transaction in transactions {
+            writeln!(file, "{}", serde_json::to_string(transaction)?)?;
+        }
+        file.sync_all()?;
+        fs::rename(tmp, &self.path)
+    }
+}
@@ -2,7 +2,7 @@ mod transaction;
 use std::time::Duration;
 
 use transaction::WithdrawalTxData;
-pub use transaction::{SignedTransaction, Transaction};
+pub use transaction::{Expiry, SignedTransaction, Transaction};
 
 mod signer;
 use signer::Signature;
@@ -12,13 +12,68 @@ mod block;
 pub use block::{Block, BlockHeader, SignedBlockHeader};
 
 mod sequencer;
-pub use sequencer::{Sequencer, TransactionSubmitter};
+pub use sequencer::{BlockSealer, KeyRotator, SealingMode, Sequencer, TransactionSubmitter};
 
 mod blockchain;
-pub use blockchain::Blockchain;
+pub use blockchain::{BlockStatus, Blockchain, ImportError, TransactionLocation};
+
+mod consensus;
+pub use consensus::{quorum, CommitCertificate, SignedVote, Vote, VoteKind};
+
+mod evidence;
+pub use evidence::{EquivocationEvidence, EvidenceStore};
+
+mod handover;
+pub use handover::{Handover, HandoverError, SignedHandover};
+
+mod state;
+pub use state::{State, StateRoots};
+
+mod prover;
+pub use prover::{prove, MockProver, Proof, ProofError, Prover, Verifier};
+
+mod batch;
+pub use batch::{derive, Batch, BatchSubmitter, DaSink, DeriveError, FileSink};
+
+mod ingress;
+pub use ingress::{check, AdmissionError, Ingress};
+
+mod journal;
+
+#[cfg(feature = "http")]
+pub mod http;
+
+mod mempool;
+pub use mempool::{Mempool, MempoolError, TransactionStatus, MEMPOOL_CAPACITY, PRICE_BUMP};
+
+mod storage;
+pub use storage::{DiskStorage, MemoryStorage, Storage, StorageError};
+
+mod archive;
+pub use archive::{export, import, ArchiveError};
+
+mod merkle;
+pub use merkle::{Frontier, MerkleProof, TREE_DEPTH};
+
+mod preconf;
+pub use preconf::{SignedSubBlock, SubBlock};
+
+mod receipt;
+pub use receipt::{SignedSoftReceipt, SoftReceipt};
+
+mod clock;
+pub use clock::{Clock, ManualClock, SystemClock};
 
 mod address;
 pub use address::Address;
 
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+
 pub const BLOCK_PERIOD: Duration = Duration::from_secs(2);
+/// The number of preconfirmation intervals per block period.
+pub const SUB_BLOCKS: u32 = 10;
+/// The number of block periods a due block may stall before its slot passes on to the next
+/// sequencer.
+pub const VIEW_TIMEOUT: u32 = 3;
 pub const CHAIN_ID: u64 = 83479;
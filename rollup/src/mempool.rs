@@ -0,0 +1,470 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, VecDeque};
+
+use alloy_primitives::B256;
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, SignedTransaction, Transaction};
+
+/// The default maximum number of transactions held by a [Mempool].
+pub const MEMPOOL_CAPACITY: usize = 4096;
+
+/// The default percentage by which a replacement transaction must raise the fees of the
+/// pending transaction it replaces.
+pub const PRICE_BUMP: u64 = 10;
+
+/// An error encountered while adding a transaction to the [Mempool].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MempoolError {
+    /// The transaction is already in the pool.
+    AlreadyKnown,
+    /// The sender has already had a transaction with the nonce included in a block.
+    NonceTooLow { expected: u64, actual: u64 },
+    /// The transaction does not raise the fees of the pending transaction with the same
+    /// sender and nonce by enough to replace it.
+    ReplacementUnderpriced,
+    /// The pool is full of transactions paying at least as much.
+    Full,
+}
+
+impl std::fmt::Display for MempoolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MempoolError::AlreadyKnown => write!(f, "transaction already known"),
+            MempoolError::NonceTooLow { expected, actual } => {
+                write!(f, "nonce too low: {actual}, expected at least {expected}")
+            }
+            MempoolError::ReplacementUnderpriced => write!(f, "replacement underpriced"),
+            MempoolError::Full => write!(f, "mempool full"),
+        }
+    }
+}
+
+impl std::error::Error for MempoolError {}
+
+/// The status of a transaction known to the [Mempool].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionStatus {
+    /// The transaction can be included in the upcoming block.
+    Pending,
+    /// The transaction waits for transactions with earlier nonces from its sender.
+    Queued,
+    /// The transaction expired before it was included and was dropped from the pool.
+    Expired,
+}
+
+/// A pooled transaction and the order it arrived in.
+#[derive(Debug, Clone)]
+struct Entry {
+    transaction: SignedTransaction,
+    arrival: u64,
+}
+
+impl Entry {
+    /// Returns the priority of the entry: the highest tip first, then the earliest arrival.
+    fn priority(&self) -> (u64, Reverse<u64>) {
+        (
+            self.transaction.transaction.effective_tip(),
+            Reverse(self.arrival),
+        )
+    }
+}
+
+/// The pool of transactions waiting to be included in a block.
+///
+/// Transactions are queued per sender by nonce. A transaction is executable once every
+/// nonce before it, starting from the sender's next nonce on the chain, is in the pool too.
+/// Executable transactions are handed out in order of effective tip, while transactions
+/// with future nonces stay queued until the gap is filled. A pending transaction is replaced
+/// by one with the same sender and nonce that raises both its fees by the price bump.
+#[derive(Debug)]
+pub struct Mempool {
+    /// The pooled transactions of each sender, by nonce.
+    senders: HashMap<Address, BTreeMap<u64, Entry>>,
+    /// The sender and nonce of each pooled transaction, by hash.
+    hashes: HashMap<B256, (Address, u64)>,
+    /// The next nonce of each sender with transactions included in the chain.
+    nonces: HashMap<Address, u64>,
+    /// The maximum number of pooled transactions.
+    capacity: usize,
+    /// The percentage by which a replacement must raise the fees of the transaction it replaces.
+    price_bump: u64,
+    /// The number of transactions added so far, used to order transactions paying equal tips.
+    arrivals: u64,
+    /// The hashes of the most recently expired transactions, oldest first.
+    expired: VecDeque<B256>,
+}
+
+impl Default for Mempool {
+    fn default() -> Self {
+        Self::new(MEMPOOL_CAPACITY)
+    }
+}
+
+impl Mempool {
+    /// Creates an empty pool holding up to the given number of transactions.
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity > 0,
+            "the mempool must hold at least one transaction"
+        );
+        Self {
+            senders: HashMap::new(),
+            hashes: HashMap::new(),
+            nonces: HashMap::new(),
+            capacity,
+            price_bump: PRICE_BUMP,
+            arrivals: 0,
+            expired: VecDeque::new(),
+        }
+    }
+
+    /// Requires replacements to raise fees by the given percentage instead of [PRICE_BUMP].
+    pub fn with_price_bump(self, price_bump: u64) -> Self {
+        Self { price_bump, ..self }
+    }
+
+    /// Returns the number of pooled transactions.
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Returns whether the pool is empty.
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Returns whether the transaction with the given hash is pooled.
+    pub fn contains(&self, hash: &B256) -> bool {
+        self.hashes.contains_key(hash)
+    }
+
+    /// Returns the status of the transaction with the given hash, if it is pooled or expired
+    /// recently.
+    pub fn status(&self, hash: &B256) -> Option<TransactionStatus> {
+        let Some((sender, nonce)) = self.hashes.get(hash) else {
+            return self
+                .expired
+                .contains(hash)
+                .then_some(TransactionStatus::Expired);
+        };
+        let queue = &self.senders[sender];
+        let executable = (self.nonce(sender)..*nonce).all(|n| queue.contains_key(&n));
+        if executable {
+            Some(TransactionStatus::Pending)
+        } else {
+            Some(TransactionStatus::Queued)
+        }
+    }
+
+    /// Returns the next nonce of the given sender on the chain.
+    pub fn nonce(&self, sender: &Address) -> u64 {
+        self.nonces.get(sender).copied().unwrap_or_default()
+    }
+
+    /// Adds a transaction to the pool, replacing the pending transaction with the same sender
+    /// and nonce if it pays enough more. When the pool is full, the lowest paying transaction
+    /// at the end of a sender's queue is evicted to make room for a better paying one.
+    pub fn add(&mut self, transaction: SignedTransaction) -> Result<(), MempoolError> {
+        let hash = transaction.transaction.hash();
+        if self.contains(&hash) {
+            return Err(MempoolError::AlreadyKnown);
+        }
+        let sender = transaction.transaction.sender();
+        let nonce = transaction.transaction.nonce();
+        let expected = self.nonce(&sender);
+        if nonce < expected {
+            return Err(MempoolError::NonceTooLow {
+                expected,
+                actual: nonce,
+            });
+        }
+        let original = self
+            .senders
+            .get(&sender)
+            .and_then(|queue| queue.get(&nonce))
+            .map(|entry| &entry.transaction.transaction);
+        if let Some(original) = original {
+            if !self.is_bumped(original, &transaction.transaction) {
+                return Err(MempoolError::ReplacementUnderpriced);
+            }
+            self.remove(&sender, nonce);
+        } else if self.len() >= self.capacity {
+            self.evict(transaction.transaction.effective_tip())?;
+        }
+        let entry = Entry {
+            transaction,
+            arrival: self.arrivals,
+        };
+        self.arrivals += 1;
+        self.senders.entry(sender).or_default().insert(nonce, entry);
+        self.hashes.insert(hash, (sender, nonce));
+        Ok(())
+    }
+
+    /// Returns whether the replacement raises both fees of the original by the price bump.
+    /// Since withdrawals pay no fees, they can only be replaced by dynamic transactions.
+    fn is_bumped(&self, original: &Transaction, replacement: &Transaction) -> bool {
+        let bumped = |old: u64, new: u64| {
+            new > old
+                && u128::from(new) * 100
+                    >= u128::from(old).saturating_mul(100 + u128::from(self.price_bump))
+        };
+        bumped(original.max_fee_per_gas(), replacement.max_fee_per_gas())
+            && bumped(
+                original.max_priority_fee_per_gas(),
+                replacement.max_priority_fee_per_gas(),
+            )
+    }
+
+    /// Evicts the lowest paying transaction among the last of each sender's queue, so that
+    /// no queue is left with a gap, provided it pays less than the given tip.
+    fn evict(&mut self, tip: u64) -> Result<(), MempoolError> {
+        let victim = self
+            .senders
+            .iter()
+            .filter_map(|(sender, queue)| queue.last_key_value().map(|(_, e)| (sender, e)))
+            .min_by_key(|(_, entry)| entry.priority())
+            .map(|(sender, entry)| (*sender, entry.transaction.clone()));
+        match victim {
+            Some((sender, victim)) if victim.transaction.effective_tip() < tip => {
+                self.remove(&sender, victim.transaction.nonce());
+                Ok(())
+            }
+            _ => Err(MempoolError::Full),
+        }
+    }
+
+    /// Removes the transaction from the given sender with the given nonce.
+    fn remove(&mut self, sender: &Address, nonce: u64) -> Option<SignedTransaction> {
+        let queue = self.senders.get_mut(sender)?;
+        let entry = queue.remove(&nonce)?;
+        if queue.is_empty() {
+            self.senders.remove(sender);
+        }
+        self.hashes.remove(&entry.transaction.transaction.hash());
+        Some(entry.transaction)
+    }
+
+    /// Returns every pooled transaction, executable or not, in the order it arrived.
+    pub fn transactions(&self) -> Vec<SignedTransaction> {
+        let mut entries: Vec<&Entry> = self.senders.values().flat_map(|q| q.values()).collect();
+        entries.sort_by_key(|entry| entry.arrival);
+        entries.into_iter().map(|e| e.transaction.clone()).collect()
+    }
+
+    /// Returns the executable transactions in the order they should be included: by
+    /// effective tip, while keeping each sender's transactions in nonce order.
+    pub fn pending(&self) -> Vec<SignedTransaction> {
+        let executable = |sender: &Address, nonce: u64| {
+            self.senders.get(sender).and_then(|queue| queue.get(&nonce))
+        };
+
+        // Start from the next nonce of every sender, then replace each transaction taken
+        // with the sender's following one.
+        let mut heap: BinaryHeap<_> = self
+            .senders
+            .keys()
+            .filter_map(|sender| {
+                let nonce = self.nonce(sender);
+                executable(sender, nonce).map(|entry| (entry.priority(), *sender, nonce))
+            })
+            .collect();
+        let mut pending = vec![];
+        while let Some((_, sender, nonce)) = heap.pop() {
+            pending.push(executable(&sender, nonce).unwrap().transaction.clone());
+            if let Some(next) = executable(&sender, nonce + 1) {
+                heap.push((next.priority(), sender, nonce + 1));
+            }
+        }
+        pending
+    }
+
+    /// Drops the transactions that have expired for a block with the given number and
+    /// timestamp, returning their hashes. The most recent are remembered as expired.
+    pub fn sweep(&mut self, number: u64, timestamp: u64) -> Vec<B256> {
+        let expired: Vec<(Address, u64)> = self
+            .senders
+            .iter()
+            .flat_map(|(sender, queue)| {
+                queue
+                    .iter()
+                    .filter(|(_, e)| e.transaction.transaction.is_expired(number, timestamp))
+                    .map(|(nonce, _)| (*sender, *nonce))
+            })
+            .collect();
+        let mut hashes = vec![];
+        for (sender, nonce) in expired {
+            let tx = self.remove(&sender, nonce).unwrap();
+            hashes.push(tx.transaction.hash());
+        }
+        self.expired.extend(hashes.iter().copied());
+        while self.expired.len() > self.capacity {
+            self.expired.pop_front();
+        }
+        hashes
+    }
+
+    /// Drops the transactions included in a finalized block, along with any other pooled
+    /// transactions their senders can no longer include.
+    pub fn remove_included(&mut self, transactions: &[SignedTransaction]) {
+        for tx in transactions {
+            let sender = tx.transaction.sender();
+            let next = tx.transaction.nonce() + 1;
+            let nonce = self.nonces.entry(sender).or_default();
+            *nonce = (*nonce).max(next);
+            let stale: Vec<u64> = self
+                .senders
+                .get(&sender)
+                .map(|queue| queue.range(..next).map(|(nonce, _)| *nonce).collect())
+                .unwrap_or_default();
+            for nonce in stale {
+                self.remove(&sender, nonce);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Expiry, Signer};
+
+    /// Signs a dynamic transaction from the signer paying the given tip.
+    fn transaction(signer: &Signer, nonce: u64, tip: u64) -> SignedTransaction {
+        let tx = Transaction::dynamic(signer.address, 1, nonce).with_fees(tip, tip);
+        SignedTransaction::new(tx, signer)
+    }
+
+    #[test]
+    fn test_mempool_ordering() {
+        let (alice, bob) = (Signer::random(), Signer::random());
+        let mut mempool = Mempool::default();
+
+        // Transactions are deduplicated.
+        let first = transaction(&alice, 0, 1);
+        mempool.add(first.clone()).unwrap();
+        assert_eq!(mempool.add(first.clone()), Err(MempoolError::AlreadyKnown));
+
+        // Future nonces are queued until the gap is filled.
+        let third = transaction(&alice, 2, 9);
+        mempool.add(third.clone()).unwrap();
+        assert_eq!(mempool.pending(), vec![first.clone()]);
+        let second = transaction(&alice, 1, 3);
+        mempool.add(second.clone()).unwrap();
+
+        // Executable transactions are ordered by tip, keeping each sender's in nonce order.
+        let other = transaction(&bob, 0, 2);
+        mempool.add(other.clone()).unwrap();
+        assert_eq!(
+            mempool.pending(),
+            vec![other.clone(), first.clone(), second.clone(), third.clone()]
+        );
+
+        // Included transactions are dropped, and their nonces can no longer be used.
+        mempool.remove_included(&[first.clone(), second]);
+        assert_eq!(mempool.pending(), vec![third, other]);
+        assert_eq!(
+            mempool.add(transaction(&alice, 1, 5)),
+            Err(MempoolError::NonceTooLow {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(mempool.len(), 2);
+    }
+
+    #[test]
+    fn test_mempool_replacement() {
+        let signer = Signer::random();
+        let mut mempool = Mempool::default().with_price_bump(20);
+        let original = transaction(&signer, 0, 10);
+        mempool.add(original.clone()).unwrap();
+
+        // A replacement must raise both fees by the price bump.
+        assert_eq!(
+            mempool.add(transaction(&signer, 0, 11)),
+            Err(MempoolError::ReplacementUnderpriced)
+        );
+        let tx = Transaction::dynamic(signer.address, 1, 0).with_fees(20, 10);
+        assert_eq!(
+            mempool.add(SignedTransaction::new(tx, &signer)),
+            Err(MempoolError::ReplacementUnderpriced)
+        );
+        let replacement = transaction(&signer, 0, 12);
+        mempool.add(replacement.clone()).unwrap();
+        assert!(!mempool.contains(&original.transaction.hash()));
+        assert_eq!(mempool.pending(), vec![replacement]);
+
+        // Withdrawals pay no fees, so only a paying transaction replaces them.
+        let withdrawal = Transaction::withdrawal(signer.address, 1, 1, 1);
+        mempool
+            .add(SignedTransaction::new(withdrawal, &signer))
+            .unwrap();
+        let withdrawal = Transaction::withdrawal(signer.address, 2, 1, 1);
+        assert_eq!(
+            mempool.add(SignedTransaction::new(withdrawal, &signer)),
+            Err(MempoolError::ReplacementUnderpriced)
+        );
+        mempool.add(transaction(&signer, 1, 1)).unwrap();
+        assert_eq!(mempool.len(), 2);
+
+        // A huge price bump rejects replacements instead of overflowing.
+        let mut mempool = Mempool::default().with_price_bump(u64::MAX);
+        mempool.add(transaction(&signer, 0, u64::MAX - 1)).unwrap();
+        assert_eq!(
+            mempool.add(transaction(&signer, 0, u64::MAX)),
+            Err(MempoolError::ReplacementUnderpriced)
+        );
+    }
+
+    #[test]
+    fn test_mempool_expiry() {
+        let signer = Signer::random();
+        let mut mempool = Mempool::default();
+        let expiring = |nonce, expiry| {
+            let tx = Transaction::dynamic(signer.address, 1, nonce).with_expiry(expiry);
+            SignedTransaction::new(tx, &signer)
+        };
+        let first = expiring(0, Expiry::Block(3));
+        let second = expiring(1, Expiry::Time(100));
+        let third = expiring(2, Expiry::Block(5));
+        for tx in [&first, &second, &third] {
+            mempool.add(tx.clone()).unwrap();
+        }
+        let [first, second, third] = [first, second, third].map(|tx| tx.transaction.hash());
+        assert_eq!(mempool.status(&third), Some(TransactionStatus::Pending));
+
+        // Transactions are dropped once their block or time has passed.
+        assert!(mempool.sweep(3, 100).is_empty());
+        assert_eq!(mempool.sweep(3, 101), vec![second]);
+        assert_eq!(mempool.sweep(4, 101), vec![first]);
+        assert_eq!(mempool.status(&first), Some(TransactionStatus::Expired));
+
+        // Later transactions from the sender wait for the expired nonces to be filled again.
+        assert_eq!(mempool.status(&third), Some(TransactionStatus::Queued));
+        assert!(mempool.pending().is_empty());
+        assert_eq!(mempool.status(&B256::random()), None);
+    }
+
+    #[test]
+    fn test_mempool_eviction() {
+        let signers: Vec<Signer> = (0..3).map(|_| Signer::random()).collect();
+        let mut mempool = Mempool::new(2);
+        let cheap = transaction(&signers[0], 0, 1);
+        let dear = transaction(&signers[1], 0, 5);
+        mempool.add(cheap.clone()).unwrap();
+        mempool.add(dear.clone()).unwrap();
+
+        // A full pool only admits transactions paying more than the cheapest it holds.
+        assert_eq!(
+            mempool.add(transaction(&signers[2], 0, 1)),
+            Err(MempoolError::Full)
+        );
+        let better = transaction(&signers[2], 0, 2);
+        mempool.add(better.clone()).unwrap();
+        assert!(!mempool.contains(&cheap.transaction.hash()));
+        assert_eq!(mempool.pending(), vec![dear, better]);
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use alloy_primitives::B256;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The depth of the incremental Merkle trees.
+pub const TREE_DEPTH: usize = 32;
+
+/// Hashes a pair of sibling nodes into their parent node.
+fn hash(left: &B256, right: &B256) -> B256 {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    B256::from_slice(&hasher.finalize())
+}
+
+/// The roots of empty subtrees of each height, starting with the empty leaf.
+static ZEROS: LazyLock<[B256; TREE_DEPTH + 1]> = LazyLock::new(|| {
+    let mut zeros = [B256::ZERO; TREE_DEPTH + 1];
+    for height in 0..TREE_DEPTH {
+        zeros[height + 1] = hash(&zeros[height], &zeros[height]);
+    }
+    zeros
+});
+
+/// The frontier of an incremental Merkle tree: the number of leaves appended and the root of
+/// the rightmost completed subtree of each height. Appending a leaf and computing the root
+/// take time proportional to the depth of the tree, without keeping the leaves.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct Frontier {
+    /// The number of leaves appended.
+    len: u64,
+    /// The root of the rightmost completed subtree of each height.
+    branches: [B256; TREE_DEPTH],
+}
+
+impl Frontier {
+    /// Appends a leaf to the tree.
+    pub fn push(&mut self, leaf: B256) {
+        let mut node = leaf;
+        for (height, branch) in self.branches.iter_mut().enumerate() {
+            if (self.len >> height) & 1 == 0 {
+                *branch = node;
+                break;
+            }
+            node = hash(branch, &node);
+        }
+        self.len += 1;
+    }
+
+    /// Returns the number of leaves appended.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns whether no leaves have been appended.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Computes the root of the tree, padding it with empty leaves.
+    pub fn root(&self) -> B256 {
+        let mut node = ZEROS[0];
+        for (height, branch) in self.branches.iter().enumerate() {
+            node = if (self.len >> height) & 1 == 1 {
+                hash(branch, &node)
+            } else {
+                hash(&node, &ZEROS[height])
+            };
+        }
+        node
+    }
+}
+
+/// An append-only Merkle tree keeping the roots of all its completed subtrees, so that the
+/// inclusion of a leaf can be proven against the root after any number of leaves in time
+/// proportional to the depth of the tree.
+#[derive(Debug, Clone)]
+pub(crate) struct Tree {
+    /// The roots of the completed subtrees of each height below the root, starting with
+    /// the leaves.
+    nodes: Vec<Vec<B256>>,
+    /// The index of every leaf, by value.
+    indexes: HashMap<B256, u64>,
+}
+
+impl Default for Tree {
+    fn default() -> Self {
+        Self {
+            nodes: vec![vec![]; TREE_DEPTH],
+            indexes: HashMap::new(),
+        }
+    }
+}
+
+impl Tree {
+    /// Appends a leaf to the tree, completing the subtrees it is the last leaf of.
+    pub fn push(&mut self, leaf: B256) {
+        let index = self.len();
+        self.indexes.entry(leaf).or_insert(index);
+        let mut node = leaf;
+        for level in self.nodes.iter_mut() {
+            level.push(node);
+            if level.len() % 2 == 1 {
+                break;
+            }
+            node = hash(&level[level.len() - 2], &node);
+        }
+    }
+
+    /// Returns the number of leaves appended.
+    pub fn len(&self) -> u64 {
+        self.nodes[0].len() as u64
+    }
+
+    /// Returns the index of the given leaf, if it has been appended.
+    pub fn index(&self, leaf: &B256) -> Option<u64> {
+        self.indexes.get(leaf).copied()
+    }
+
+    /// Creates a proof of inclusion of the leaf at the given index against the root of the
+    /// tree when it held the first `count` leaves.
+    pub fn proof(&self, index: u64, count: u64) -> Option<MerkleProof> {
+        if index >= count || count > self.len() {
+            return None;
+        }
+
+        // Fold up the right edge of the tree, computing the root of the subtree of each
+        // height that holds the first missing leaf, as the frontier does.
+        let mut edges = [ZEROS[0]; TREE_DEPTH];
+        for height in 1..TREE_DEPTH {
+            let position = count >> (height - 1);
+            edges[height] = if position & 1 == 1 {
+                hash(
+                    &self.nodes[height - 1][position as usize - 1],
+                    &edges[height - 1],
+                )
+            } else {
+                hash(&edges[height - 1], &ZEROS[height - 1])
+            };
+        }
+
+        // Each sibling is either a completed subtree, the subtree on the right edge or empty.
+        let siblings = (0..TREE_DEPTH)
+            .map(|height| {
+                let sibling = (index >> height) ^ 1;
+                let edge = count >> height;
+                if sibling < edge {
+                    self.nodes[height][sibling as usize]
+                } else if sibling == edge {
+                    edges[height]
+                } else {
+                    ZEROS[height]
+                }
+            })
+            .collect();
+        Some(MerkleProof {
+            leaf: self.nodes[0][index as usize],
+            index,
+            siblings,
+        })
+    }
+}
+
+/// A proof of inclusion of a leaf in an incremental Merkle tree.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct MerkleProof {
+    /// The leaf proven to be included.
+    pub leaf: B256,
+    /// The position of the leaf in the tree.
+    pub index: u64,
+    /// The sibling nodes on the path from the leaf to the root.
+    pub siblings: Vec<B256>,
+}
+
+impl MerkleProof {
+    /// Creates a proof of inclusion of the leaf at the given index in the tree with the given leaves.
+    pub fn new(leaves: &[B256], index: u64) -> Option<Self> {
+        let mut tree = Tree::default();
+        leaves.iter().for_each(|leaf| tree.push(*leaf));
+        tree.proof(index, tree.len())
+    }
+
+    /// Computes the root of the tree the leaf is included in.
+    pub fn root(&self) -> B256 {
+        self.siblings
+            .iter()
+            .enumerate()
+            .fold(self.leaf, |node, (height, sibling)| {
+                if (self.index >> height) & 1 == 0 {
+                    hash(&node, sibling)
+                } else {
+                    hash(sibling, &node)
+                }
+            })
+    }
+
+    /// Verifies the proof against the given hex encoded root, as recorded in block headers.
+    pub fn verify(&self, root: &str) -> bool {
+        self.siblings.len() == TREE_DEPTH && format!("{:x}", self.root()) == root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Computes the hex encoded root of the tree with the given leaves.
+    fn root(leaves: &[B256]) -> String {
+        let mut frontier = Frontier::default();
+        leaves.iter().for_each(|leaf| frontier.push(*leaf));
+        format!("{:x}", frontier.root())
+    }
+
+    #[test]
+    fn test_frontier() {
+        // The root of the empty tree is the root of the empty subtree of full height.
+        assert_eq!(Frontier::default().root(), ZEROS[TREE_DEPTH]);
+
+        // The root after each appended leaf matches the root the leaf is proven against.
+        let leaves: Vec<B256> = (0..9).map(|_| B256::random()).collect();
+        for len in 1..=leaves.len() {
+            let proof = MerkleProof::new(&leaves[..len], len as u64 - 1).unwrap();
+            assert_eq!(root(&leaves[..len]), format!("{:x}", proof.root()));
+        }
+    }
+
+    #[test]
+    fn test_tree() {
+        let leaves: Vec<B256> = (0..13).map(|_| B256::random()).collect();
+        let mut tree = Tree::default();
+        leaves.iter().for_each(|leaf| tree.push(*leaf));
+        assert_eq!(tree.len(), 13);
+        assert_eq!(tree.index(&leaves[7]), Some(7));
+        assert_eq!(tree.index(&B256::random()), None);
+
+        // Every leaf can be proven against the root of the tree after any later leaf.
+        for count in 1..=leaves.len() {
+            let root = root(&leaves[..count]);
+            for index in 0..count as u64 {
+                assert!(tree.proof(index, count as u64).unwrap().verify(&root));
+            }
+            assert!(tree.proof(count as u64, count as u64).is_none());
+        }
+        assert!(tree.proof(0, 14).is_none());
+    }
+
+    #[test]
+    fn test_merkle_proof() {
+        let leaves: Vec<B256> = (0..5).map(|_| B256::random()).collect();
+        let root = root(&leaves);
+
+        // Every leaf can be proven against the root.
+        for index in 0..leaves.len() as u64 {
+            let proof = MerkleProof::new(&leaves, index).unwrap();
+            assert!(proof.verify(&root));
+        }
+        assert!(MerkleProof::new(&leaves, 5).is_none());
+
+        // A proof does not verify for a different leaf, position or root.
+        let mut proof = MerkleProof::new(&leaves, 2).unwrap();
+        proof.index = 3;
+        assert!(!proof.verify(&root));
+        proof.index = 2;
+        proof.leaf = leaves[1];
+        assert!(!proof.verify(&root));
+        let proof = MerkleProof::new(&leaves, 2).unwrap();
+        assert!(!proof.verify(&self::root(&leaves[..4])));
+    }
+}
@@ -0,0 +1,123 @@
+use alloy_primitives::{keccak256, B256};
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, Block, Signature, Signer};
+
+/// An incremental update on the upcoming block, listing the transactions the sequencer
+/// has committed to include so far.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct SubBlock {
+    /// The address of the sequencer sealing the upcoming block.
+    pub sequencer: Address,
+    /// The number of the upcoming block.
+    pub number: u64,
+    /// The position of the update among those for the upcoming block, starting at zero.
+    pub index: u64,
+    /// The hashes of the transactions committed so far, in the order they will be sealed.
+    pub transactions: Vec<B256>,
+}
+
+impl SubBlock {
+    /// Computes the hash of the sub-block.
+    pub fn hash(&self) -> B256 {
+        let bytes = bincode::serialize(self).unwrap();
+        keccak256(bytes)
+    }
+}
+
+/// A sub-block signed by the sequencer, preconfirming its transactions ahead of the block.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct SignedSubBlock {
+    sub_block: SubBlock,
+    signature: Signature,
+}
+
+impl SignedSubBlock {
+    /// Creates a new signed sub-block with the given sub-block and signer.
+    pub fn new(sub_block: SubBlock, signer: &Signer) -> Self {
+        let signature = signer.sign(sub_block.hash());
+        Self {
+            sub_block,
+            signature,
+        }
+    }
+
+    /// Returns the sub-block that was signed.
+    pub fn sub_block(&self) -> &SubBlock {
+        &self.sub_block
+    }
+
+    /// Verifies that the sub-block was signed by the sequencer it names.
+    pub fn verify(&self) -> bool {
+        self.signature.recover(self.sub_block.hash()) == Some(self.sub_block.sequencer)
+    }
+
+    /// Returns whether the block honours the preconfirmation, i.e. it was sealed by the same
+    /// sequencer at the same number and begins with the preconfirmed transactions in order.
+    pub fn is_consistent(&self, block: &Block) -> bool {
+        let header = block.header();
+        header.sequencer == self.sub_block.sequencer
+            && header.number == self.sub_block.number
+            && block.transactions().len() >= self.sub_block.transactions.len()
+            && block
+                .transactions()
+                .iter()
+                .zip(&self.sub_block.transactions)
+                .all(|(tx, hash)| tx.transaction.hash() == *hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlockHeader, SignedBlockHeader, SignedTransaction, Transaction};
+
+    #[test]
+    fn test_preconfirmation_consistency() {
+        let signer = Signer::random();
+        let transactions: Vec<SignedTransaction> = (0..3)
+            .map(|nonce| {
+                SignedTransaction::new(Transaction::dynamic(signer.address, 1, nonce), &signer)
+            })
+            .collect();
+        let preconfirm = |hashes: Vec<B256>| {
+            let sub_block = SubBlock {
+                sequencer: signer.address,
+                number: 0,
+                index: 0,
+                transactions: hashes,
+            };
+            SignedSubBlock::new(sub_block, &signer)
+        };
+        let header = BlockHeader {
+            sequencer: signer.address,
+            number: 0,
+            view: 0,
+            timestamp: 0,
+            parent_digest: None,
+            withdrawals_root: "0".to_string(),
+            transactions_root: "0".to_string(),
+            handover: None,
+        };
+        let block = Block::new(
+            SignedBlockHeader::new(header, &signer),
+            transactions.clone(),
+        );
+        let hashes: Vec<B256> = transactions
+            .iter()
+            .map(|tx| tx.transaction.hash())
+            .collect();
+
+        // A block is consistent with preconfirmations of a prefix of its transactions.
+        let preconfirmation = preconfirm(hashes[..2].to_vec());
+        assert!(preconfirmation.verify());
+        assert!(preconfirmation.is_consistent(&block));
+        assert!(preconfirm(hashes.clone()).is_consistent(&block));
+
+        // A block that reorders or leaves out preconfirmed transactions is not.
+        assert!(!preconfirm(vec![hashes[1], hashes[0]]).is_consistent(&block));
+        let mut extra = hashes;
+        extra.push(B256::random());
+        assert!(!preconfirm(extra).is_consistent(&block));
+    }
+}
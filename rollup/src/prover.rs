@@ -0,0 +1,262 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy_primitives::keccak256;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{Blockchain, SignedTransaction, State, StateRoots};
+
+/// A validity proof that applying a block's transactions to its pre-state yields its post-state.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct Proof {
+    /// The opaque proof data produced by the [Prover].
+    pub data: Vec<u8>,
+}
+
+/// An error encountered while proving a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofError {
+    /// Applying the transactions to the pre-state does not yield the claimed post-state.
+    InvalidTransition,
+}
+
+impl std::fmt::Display for ProofError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProofError::InvalidTransition => write!(f, "invalid state transition"),
+        }
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+/// Produces validity proofs of block execution.
+pub trait Prover {
+    /// Proves that applying the transactions to the pre-state yields the post-state.
+    fn prove(
+        &self,
+        pre_state: &State,
+        transactions: &[SignedTransaction],
+        post_state: &StateRoots,
+    ) -> Result<Proof, ProofError>;
+}
+
+/// Verifies validity proofs of block execution against their public inputs.
+pub trait Verifier {
+    /// Verifies the proof that applying the transactions to the state committed to by the
+    /// pre-state roots yields the state committed to by the post-state roots.
+    fn verify(
+        &self,
+        pre_state: &StateRoots,
+        transactions: &[SignedTransaction],
+        post_state: &StateRoots,
+        proof: &Proof,
+    ) -> bool;
+}
+
+/// A mock [Prover] and [Verifier] which proves blocks by deterministically re-executing them.
+/// Its proofs are a digest of the public inputs and carry no cryptographic soundness.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MockProver;
+
+impl MockProver {
+    /// Computes the digest of the public inputs of a proof.
+    fn digest(
+        pre_state: &StateRoots,
+        transactions: &[SignedTransaction],
+        post_state: &StateRoots,
+    ) -> Vec<u8> {
+        let hashes: Vec<_> = transactions
+            .iter()
+            .map(|tx| tx.transaction.hash())
+            .collect();
+        let bytes = bincode::serialize(&(pre_state, hashes, post_state)).unwrap();
+        keccak256(bytes).to_vec()
+    }
+}
+
+impl Prover for MockProver {
+    fn prove(
+        &self,
+        pre_state: &State,
+        transactions: &[SignedTransaction],
+        post_state: &StateRoots,
+    ) -> Result<Proof, ProofError> {
+        let mut state = pre_state.clone();
+        state.apply(transactions);
+        if state.roots() != *post_state {
+            return Err(ProofError::InvalidTransition);
+        }
+        Ok(Proof {
+            data: Self::digest(&pre_state.roots(), transactions, post_state),
+        })
+    }
+}
+
+impl Verifier for MockProver {
+    fn verify(
+        &self,
+        pre_state: &StateRoots,
+        transactions: &[SignedTransaction],
+        post_state: &StateRoots,
+        proof: &Proof,
+    ) -> bool {
+        proof.data == Self::digest(pre_state, transactions, post_state)
+    }
+}
+
+/// Proves the blocks of the [Blockchain] in order as they are finalized, checking for new
+/// blocks every `period`, and marks each proven once its proof is verified. Proving resumes
+/// after the blocks proven before a restart.
+pub async fn prove(
+    blockchain: Arc<Mutex<Blockchain>>,
+    prover: impl Prover,
+    verifier: impl Verifier,
+    period: Duration,
+) {
+    // Rebuild the pre-state of the first block left to prove.
+    let mut state = State::default();
+    let proven = blockchain.lock().await.proofs.len() as u64;
+    for number in 0..proven {
+        match blockchain.lock().await.block(number) {
+            Ok(Some(block)) => state.apply(&block.transactions),
+            Ok(None) => {
+                warn!("Proven block {} is missing", number);
+                return;
+            }
+            Err(e) => {
+                warn!("Failed to read proven block {}: {}", number, e);
+                return;
+            }
+        }
+    }
+    if proven > 0 {
+        info!("Resuming proving after block {}", proven - 1);
+    }
+
+    let mut timer = tokio::time::interval(period);
+    loop {
+        timer.tick().await;
+        loop {
+            // Retrieve the next block to prove.
+            let block = {
+                let chain = blockchain.lock().await;
+                chain.block(chain.proofs.len() as u64)
+            };
+            let block = match block {
+                Ok(Some(block)) => block,
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Failed to read the next block to prove: {}", e);
+                    break;
+                }
+            };
+
+            // Prove and verify the block's execution against the pre-state.
+            let number = block.number();
+            let post_state = block.header().state_roots();
+            let proof = match prover.prove(&state, &block.transactions, &post_state) {
+                Ok(proof) => proof,
+                Err(e) => {
+                    warn!("Failed to prove block {}: {}", number, e);
+                    break;
+                }
+            };
+            if !verifier.verify(&state.roots(), &block.transactions, &post_state, &proof) {
+                warn!("Invalid proof for block {}", number);
+                break;
+            }
+            if let Err(e) = blockchain.lock().await.push_proof(proof) {
+                warn!("Failed to store proof of block {}: {}", number, e);
+                break;
+            }
+            state.apply(&block.transactions);
+            info!("Proven block: {}", number);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::certified_block;
+    use crate::{BlockStatus, DiskStorage, Signer, Transaction};
+
+    #[test]
+    fn test_mock_prover() {
+        let signer = Signer::random();
+        let transactions = vec![
+            SignedTransaction::new(Transaction::dynamic(signer.address, 1, 0), &signer),
+            SignedTransaction::new(Transaction::withdrawal(signer.address, 1, 1, 2), &signer),
+        ];
+        let pre_state = State::default();
+        let mut post_state = pre_state.clone();
+        post_state.apply(&transactions);
+        let post_state = post_state.roots();
+
+        // A valid transition is proven and verified.
+        let proof = MockProver
+            .prove(&pre_state, &transactions, &post_state)
+            .unwrap();
+        assert!(MockProver.verify(&pre_state.roots(), &transactions, &post_state, &proof));
+
+        // The proof does not verify against other transactions.
+        assert!(!MockProver.verify(&pre_state.roots(), &transactions[..1], &post_state, &proof));
+
+        // An invalid transition cannot be proven.
+        assert_eq!(
+            MockProver.prove(&pre_state, &transactions[..1], &post_state),
+            Err(ProofError::InvalidTransition)
+        );
+    }
+
+    /// Runs the prover over the chain for long enough to prove the blocks already in it.
+    async fn run_prover(chain: &Arc<Mutex<Blockchain>>) {
+        let period = Duration::from_millis(10);
+        let task = tokio::spawn(prove(chain.clone(), MockProver, MockProver, period));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        task.abort();
+        // Wait for the task to release the chain.
+        let _ = task.await;
+    }
+
+    #[tokio::test]
+    async fn test_prove_resumes() {
+        let signer = Signer::random();
+        let datadir = std::env::temp_dir().join(format!("prover-{}", signer.address));
+        let open = || {
+            let storage = DiskStorage::open(&datadir).unwrap();
+            Blockchain::new(vec![signer.address])
+                .with_storage(storage)
+                .unwrap()
+        };
+        let seal = |chain: &mut Blockchain| {
+            let transfer = Transaction::dynamic(signer.address, 1, chain.height());
+            let transactions = vec![SignedTransaction::new(transfer, &signer)];
+            let block = certified_block(chain, &signer, transactions);
+            chain.import(block).unwrap();
+        };
+
+        // Finalized blocks are proven in order.
+        let mut chain = open();
+        seal(&mut chain);
+        let chain = Arc::new(Mutex::new(chain));
+        run_prover(&chain).await;
+        assert_eq!(chain.lock().await.status(0), Some(BlockStatus::Proven));
+        drop(chain);
+
+        // Proven blocks stay proven across a restart, and proving picks up after them.
+        let mut chain = open();
+        assert_eq!(chain.status(0), Some(BlockStatus::Proven));
+        seal(&mut chain);
+        assert_eq!(chain.status(1), Some(BlockStatus::Sealed));
+        let chain = Arc::new(Mutex::new(chain));
+        run_prover(&chain).await;
+        assert_eq!(chain.lock().await.status(1), Some(BlockStatus::Proven));
+        assert_eq!(chain.lock().await.proofs.len(), 2);
+        drop(chain);
+        std::fs::remove_dir_all(datadir).unwrap();
+    }
+}
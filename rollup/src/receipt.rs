@@ -0,0 +1,123 @@
+use alloy_primitives::{keccak256, B256};
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, Block, Signature, Signer};
+
+/// A sequencer's promise to include a transaction at a position in an upcoming block.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct SoftReceipt {
+    /// The address of the sequencer making the promise.
+    pub sequencer: Address,
+    /// The hash of the transaction.
+    pub tx_hash: B256,
+    /// The number of the block the transaction will be included in.
+    pub number: u64,
+    /// The position of the transaction in the block.
+    pub position: u64,
+}
+
+impl SoftReceipt {
+    /// Computes the hash of the receipt.
+    pub fn hash(&self) -> B256 {
+        let bytes = bincode::serialize(self).unwrap();
+        keccak256(bytes)
+    }
+}
+
+/// A soft receipt signed by the sequencer. Together with a finalized block that does not
+/// honour it, the receipt proves that the sequencer broke its promise.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct SignedSoftReceipt {
+    receipt: SoftReceipt,
+    signature: Signature,
+}
+
+impl SignedSoftReceipt {
+    /// Creates a new signed receipt with the given receipt and signer.
+    pub fn new(receipt: SoftReceipt, signer: &Signer) -> Self {
+        let signature = signer.sign(receipt.hash());
+        Self { receipt, signature }
+    }
+
+    /// Returns the receipt that was signed.
+    pub fn receipt(&self) -> &SoftReceipt {
+        &self.receipt
+    }
+
+    /// Verifies that the receipt was signed by the sequencer it names.
+    pub fn verify(&self) -> bool {
+        self.signature.recover(self.receipt.hash()) == Some(self.receipt.sequencer)
+    }
+
+    /// Returns whether the block with the receipt's number honours it, i.e. it was sealed by
+    /// the promising sequencer and includes the transaction at the promised position.
+    pub fn is_honoured(&self, block: &Block) -> bool {
+        block.number() == self.receipt.number
+            && block.header().sequencer == self.receipt.sequencer
+            && block
+                .transactions()
+                .get(self.receipt.position as usize)
+                .is_some_and(|tx| tx.transaction.hash() == self.receipt.tx_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlockHeader, SignedBlockHeader, SignedTransaction, Transaction};
+
+    #[test]
+    fn test_soft_receipt() {
+        let signer = Signer::random();
+        let transaction =
+            SignedTransaction::new(Transaction::dynamic(signer.address, 1, 0), &signer);
+        let receipt = |number, position| {
+            let receipt = SoftReceipt {
+                sequencer: signer.address,
+                tx_hash: transaction.transaction.hash(),
+                number,
+                position,
+            };
+            SignedSoftReceipt::new(receipt, &signer)
+        };
+        let header = BlockHeader {
+            sequencer: signer.address,
+            number: 3,
+            view: 0,
+            timestamp: 0,
+            parent_digest: None,
+            withdrawals_root: "0".to_string(),
+            transactions_root: "0".to_string(),
+            handover: None,
+        };
+        let block = Block::new(
+            SignedBlockHeader::new(header, &signer),
+            vec![transaction.clone()],
+        );
+
+        // The receipt is honoured by the block including the transaction at the position.
+        assert!(receipt(3, 0).verify());
+        assert!(receipt(3, 0).is_honoured(&block));
+
+        // Another position or block breaks the promise.
+        assert!(!receipt(3, 1).is_honoured(&block));
+        assert!(!receipt(4, 0).is_honoured(&block));
+
+        // So does the block being sealed by another sequencer.
+        let other = Signer::random();
+        let header = BlockHeader {
+            sequencer: other.address,
+            ..block.header().clone()
+        };
+        let block = Block::new(
+            SignedBlockHeader::new(header, &other),
+            vec![transaction.clone()],
+        );
+        assert!(!receipt(3, 0).is_honoured(&block));
+
+        // A receipt claiming another sequencer does not verify.
+        let mut forged = receipt(3, 0);
+        forged.receipt.sequencer = Signer::random().address;
+        assert!(!forged.verify());
+    }
+}
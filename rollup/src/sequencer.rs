@@ -1,154 +1,1007 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
-use log::info;
-use p2p::GossipMessage;
+use alloy_primitives::B256;
+use log::{debug, info, warn};
+use p2p::{
+    GossipMessage, BLOCKS_TOPIC, EVIDENCE_TOPIC, PRECONFIRMATIONS_TOPIC, PROPOSALS_TOPIC,
+    TRANSACTIONS_TOPIC, VOTES_TOPIC,
+};
+use serde::Serialize;
 use serde_json::json;
-use tokio::{
-    sync::{
-        mpsc::{Receiver, Sender},
-        Mutex,
-    },
-    task,
+use tokio::sync::{
+    broadcast,
+    mpsc::{self, Receiver, Sender},
+    oneshot, Mutex, Notify,
 };
 
+use crate::consensus::Round;
 use crate::{
-    Block, BlockHeader, Blockchain, SignedBlockHeader, SignedTransaction, Signer, Transaction,
-    BLOCK_PERIOD,
+    quorum, Address, AdmissionError, Block, BlockHeader, Blockchain, Clock, CommitCertificate,
+    EquivocationEvidence, EvidenceStore, Expiry, Handover, HandoverError, Ingress, Mempool,
+    SignedBlockHeader, SignedHandover, SignedSoftReceipt, SignedSubBlock, SignedTransaction,
+    SignedVote, Signer, SoftReceipt, SubBlock, SystemClock, Vote, VoteKind, BLOCK_PERIOD,
+    SUB_BLOCKS, VIEW_TIMEOUT,
 };
 
+/// A transaction submitted directly to a [Sequencer], awaiting its admission and soft receipt.
+type Submission = (
+    SignedTransaction,
+    oneshot::Sender<Result<Option<SignedSoftReceipt>, AdmissionError>>,
+);
+
 pub struct TransactionSubmitter {
-    transactions_pool: Arc<Mutex<Vec<SignedTransaction>>>,
+    ingress: Ingress,
     outbound: Sender<(Vec<u8>, String)>,
+    /// Channel for handing transactions to a local sequencer, if any.
+    submissions: Option<Sender<Submission>>,
 }
 
 impl TransactionSubmitter {
-    pub fn new(
-        transactions_pool: Arc<Mutex<Vec<SignedTransaction>>>,
-        outbound: Sender<(Vec<u8>, String)>,
-    ) -> Self {
+    pub fn new(ingress: Ingress, outbound: Sender<(Vec<u8>, String)>) -> Self {
         TransactionSubmitter {
-            transactions_pool,
+            ingress,
             outbound,
+            submissions: None,
         }
     }
 
-    pub async fn submit(&self, transaction: SignedTransaction) {
-        let transactions_pool = self.transactions_pool.clone();
-        transactions_pool.lock().await.push(transaction.clone());
-        self.outbound
-            .send((
-                json!(transaction).to_string().as_bytes().to_vec(),
-                "transactions".to_string(),
-            ))
-            .await
-            .unwrap();
+    /// Submits a transaction and gossips it to the other nodes once admitted to the mempool.
+    /// When submitted to a local sequencer, returns its soft receipt if it was able to promise
+    /// a position in its upcoming block. Errors as unavailable if the sequencer has stopped.
+    pub async fn submit(
+        &self,
+        transaction: SignedTransaction,
+    ) -> Result<Option<SignedSoftReceipt>, AdmissionError> {
+        let receipt = match &self.submissions {
+            Some(submissions) => {
+                let (reply, receipt) = oneshot::channel();
+                let stopped = || AdmissionError::Unavailable("sequencer stopped".to_string());
+                submissions
+                    .send((transaction.clone(), reply))
+                    .await
+                    .map_err(|_| stopped())?;
+                receipt.await.map_err(|_| stopped())??
+            }
+            None => {
+                self.ingress.admit(transaction.clone()).await?;
+                None
+            }
+        };
+        // The transaction is admitted even if it cannot be gossiped.
+        let msg = (
+            json!(transaction).to_string().as_bytes().to_vec(),
+            TRANSACTIONS_TOPIC.to_string(),
+        );
+        if self.outbound.send(msg).await.is_err() {
+            warn!("Failed to gossip transaction: network stopped");
+        }
+        Ok(receipt)
+    }
+}
+
+/// A requested handover of sequencing rights to a new signer.
+struct Rotation {
+    /// The signer taking over.
+    next: Signer,
+    /// The number of the first block to be sealed by the new signer.
+    effective_at: u64,
+}
+
+/// Requests key rotations from a running [Sequencer].
+pub struct KeyRotator {
+    rotation: Arc<Mutex<Option<Rotation>>>,
+    /// The address of the key the sequencer was created with, which must sign handovers.
+    current: Address,
+    /// The key to hand sequencing over to, if the sequencer holds one.
+    next: Option<Signer>,
+}
+
+impl KeyRotator {
+    /// Requests that sequencing is handed over as described by the given handover, which must
+    /// be signed by the key the sequencer was created with and name the key configured with
+    /// [Sequencer::with_next_key]. The handover is recorded in the next sealed block and
+    /// replaces any earlier request that has not yet been recorded.
+    pub async fn rotate(&self, handover: &SignedHandover) -> Result<(), HandoverError> {
+        let Handover {
+            current,
+            next,
+            effective_at,
+        } = handover.handover();
+        if !handover.verify() || *current != self.current {
+            return Err(HandoverError::InvalidSignature);
+        }
+        let next = match &self.next {
+            Some(signer) if signer.address == *next => signer.clone(),
+            _ => return Err(HandoverError::UnknownKey(*next)),
+        };
+        *self.rotation.lock().await = Some(Rotation {
+            next,
+            effective_at: *effective_at,
+        });
+        Ok(())
+    }
+}
+
+/// Requests blocks from a running [Sequencer] sealing in [SealingMode::Manual].
+pub struct BlockSealer {
+    requests: Arc<Notify>,
+}
+
+impl BlockSealer {
+    /// Requests that the next block is sealed as soon as it is this sequencer's turn.
+    pub fn seal(&self) {
+        self.requests.notify_one();
     }
 }
 
+/// When a [Sequencer] seals blocks in its slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SealingMode {
+    /// Seal a block every block period.
+    Interval,
+    /// Seal a block as soon as a transaction arrives.
+    Instant,
+    /// Seal a block only when requested through a [BlockSealer].
+    Manual,
+    /// Seal a block every block period if there are pending transactions, or once the
+    /// given time has passed since the previous block.
+    SkipEmpty { max_idle: Duration },
+}
+
 /// Permissioned entity responsible for maintaining the canonical [Blockchain].
-/// Receives transactions directly and seals them into blocks.
+/// Receives transactions directly and proposes blocks in its turn. Blocks are finalized
+/// once a quorum of the sequencer committee has prevoted and precommitted to them.
 pub struct Sequencer {
-    /// The sequencer's signer used to sign blocks.
+    /// The sequencer's signer used to sign blocks and votes.
     signer: Signer,
     /// The blockchain maintained by the sequencer.
     blockchain: Arc<Mutex<Blockchain>>,
-    /// The pool of transactions to be included in the next block.
-    transactions_pool: Arc<Mutex<Vec<SignedTransaction>>>,
-    /// The pool of withdrawal transactions to be included in the next block.
-    withdrawals_pool: Vec<SignedTransaction>,
+    /// The path through which transactions enter the mempool for upcoming blocks.
+    ingress: Ingress,
+    /// The clock used to timestamp blocks.
+    clock: Arc<dyn Clock>,
     /// Interval of time between blocks.
-    block_timer: tokio::time::Interval,
+    block_period: Duration,
+    /// When blocks are sealed.
+    mode: SealingMode,
+    /// Channel for receiving transactions submitted directly to this sequencer.
+    submissions: Receiver<Submission>,
+    /// Channel handed to [TransactionSubmitter]s for submitting transactions.
+    submitter: Sender<Submission>,
+    /// Notified when a block is requested through a [BlockSealer].
+    requests: Arc<Notify>,
+    /// The requested handover yet to be recorded in a block.
+    rotation: Arc<Mutex<Option<Rotation>>>,
+    /// The key to hand sequencing over to once a handover is requested.
+    next_key: Option<Signer>,
+    /// The recorded handover yet to take effect.
+    next: Option<Rotation>,
+    /// Channel for receiving gossiped transactions, proposals, votes and blocks.
+    inbound: Receiver<GossipMessage>,
+    /// Channel for gossiping proposals, votes and finalized blocks.
+    outbound: Sender<(Vec<u8>, String)>,
+    /// The voting rounds for the head of the chain and later blocks, by block number.
+    rounds: BTreeMap<u64, Round>,
+    /// The evidence of equivocating sequencers collected by this node.
+    evidence: Arc<Mutex<EvidenceStore>>,
+    /// Channel for notifying subscribers of finalized blocks.
+    finalized: broadcast::Sender<Block>,
+    /// The transactions preconfirmed for this sequencer's upcoming block, in order, kept
+    /// until the block is finalized so that a failed round re-proposes them.
+    preconfirmed: Vec<SignedTransaction>,
+    /// The latest preconfirmation issued by this sequencer.
+    preconfirmation: Option<SignedSubBlock>,
+    /// Channel for notifying subscribers of issued preconfirmations.
+    preconfirmations: broadcast::Sender<SignedSubBlock>,
 }
 
 impl Sequencer {
     /// Creates a new permissioned [Sequencer].
     pub fn new(
         signer: impl Into<Signer>,
-        transactions_pool: Arc<Mutex<Vec<SignedTransaction>>>,
+        mempool: Arc<Mutex<Mempool>>,
         blockchain: Arc<Mutex<Blockchain>>,
-        mut inbound: Receiver<GossipMessage>,
+        inbound: Receiver<GossipMessage>,
+        outbound: Sender<(Vec<u8>, String)>,
+        evidence: Arc<Mutex<EvidenceStore>>,
     ) -> Self {
-        let tx_pool = transactions_pool.clone();
-        task::spawn(async move {
-            loop {
-                if let Some(msg) = inbound.recv().await {
-                    match msg.topic.as_str() {
-                        "transactions" => {
-                            let transaction: SignedTransaction =
-                                serde_json::from_slice(&msg.data).unwrap();
-                            tx_pool.lock().await.push(transaction);
-                        }
-                        "block" => {
-                            unimplemented!("Handle incoming blocks");
-                        }
-                        _ => {}
-                    }
-                }
-            }
-        });
+        let (submitter, submissions) = mpsc::channel(256);
         Sequencer {
             signer: signer.into(),
-            transactions_pool,
+            ingress: Ingress::new(mempool, blockchain.clone()),
             blockchain,
-            withdrawals_pool: vec![],
-            block_timer: tokio::time::interval(BLOCK_PERIOD),
+            clock: Arc::new(SystemClock),
+            block_period: BLOCK_PERIOD,
+            mode: SealingMode::Interval,
+            submissions,
+            submitter,
+            requests: Arc::new(Notify::new()),
+            rotation: Arc::new(Mutex::new(None)),
+            next_key: None,
+            next: None,
+            inbound,
+            outbound,
+            rounds: BTreeMap::new(),
+            evidence,
+            finalized: broadcast::channel(64).0,
+            preconfirmed: vec![],
+            preconfirmation: None,
+            preconfirmations: broadcast::channel(64).0,
+        }
+    }
+
+    /// Uses the given clock to timestamp blocks instead of the system clock.
+    pub fn with_clock(self, clock: impl Clock + 'static) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(clock);
+        Sequencer {
+            ingress: self.ingress.with_clock(clock.clone()),
+            clock,
+            ..self
         }
     }
 
+    /// Seals blocks with the given interval of time between them instead of [BLOCK_PERIOD].
+    pub fn with_block_period(self, block_period: Duration) -> Self {
+        Sequencer {
+            block_period,
+            ..self
+        }
+    }
+
+    /// Seals blocks in the given mode instead of every block period.
+    pub fn with_mode(self, mode: SealingMode) -> Self {
+        Sequencer { mode, ..self }
+    }
+
+    /// Hands sequencing over to the given key once a handover is requested through a
+    /// [KeyRotator]. The key never leaves this node.
+    pub fn with_next_key(self, next_key: impl Into<Signer>) -> Self {
+        Sequencer {
+            next_key: Some(next_key.into()),
+            ..self
+        }
+    }
+
+    /// Journals admitted transactions to the file at the given path, first restoring those
+    /// journaled by a previous run that are still valid. Returns the number restored.
+    pub async fn open_journal(&self, path: impl Into<PathBuf>) -> io::Result<usize> {
+        self.ingress.open_journal(path).await
+    }
+
+    /// Returns a [TransactionSubmitter] handing transactions to this sequencer.
+    pub fn submitter(&self) -> TransactionSubmitter {
+        TransactionSubmitter {
+            ingress: self.ingress.clone(),
+            outbound: self.outbound.clone(),
+            submissions: Some(self.submitter.clone()),
+        }
+    }
+
+    /// Returns a [BlockSealer] for requesting blocks from this sequencer.
+    pub fn sealer(&self) -> BlockSealer {
+        BlockSealer {
+            requests: self.requests.clone(),
+        }
+    }
+
+    /// Returns a [KeyRotator] for requesting handovers from this sequencer.
+    pub fn rotator(&self) -> KeyRotator {
+        KeyRotator {
+            rotation: self.rotation.clone(),
+            current: self.signer.address,
+            next: self.next_key.clone(),
+        }
+    }
+
+    /// Subscribes to the blocks finalized by this sequencer, in block order.
+    pub fn subscribe(&self) -> broadcast::Receiver<Block> {
+        self.finalized.subscribe()
+    }
+
+    /// Returns the channel of preconfirmations issued by this sequencer, for subscribing to.
+    pub fn preconfirmations(&self) -> broadcast::Sender<SignedSubBlock> {
+        self.preconfirmations.clone()
+    }
+
     /// Runs the sequencer's main loop.
+    /// Blocks are proposed in the sequencer's own slot, while gossiped messages from the
+    /// other nodes drive the voting rounds.
     pub async fn run(&mut self) {
+        self.resume().await;
+        let mut block_timer = tokio::time::interval(self.block_period);
+        // Delay rather than burst missed ticks so that a slow round is not mistaken for a stall.
+        block_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let sub_block_period = (self.block_period / SUB_BLOCKS).max(Duration::from_millis(1));
+        let mut sub_block_timer = tokio::time::interval(sub_block_period);
+        sub_block_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut last_head = None;
         loop {
-            self.block_timer.tick().await;
+            tokio::select! {
+                _ = block_timer.tick() => {
+                    let head = self.head().await.map(|b| b.hash());
+                    let stalled = head == last_head;
+                    last_head = head;
+                    self.tick(stalled, false).await;
+                }
+                _ = sub_block_timer.tick() => self.preconfirm().await,
+                Some((transaction, reply)) = self.submissions.recv() => {
+                    let _ = reply.send(self.submit(transaction).await);
+                    if self.mode == SealingMode::Instant {
+                        self.tick(false, false).await;
+                    }
+                }
+                _ = self.requests.notified(), if self.mode == SealingMode::Manual => {
+                    self.tick(false, true).await;
+                }
+                Some(msg) = self.inbound.recv() => {
+                    let transaction = msg.topic.as_str() == TRANSACTIONS_TOPIC;
+                    self.handle(msg).await;
+                    if transaction && self.mode == SealingMode::Instant {
+                        self.tick(false, false).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Catches the mempool up with the blocks already in the chain, e.g. when reloaded from
+    /// storage after a restart, so that their transactions are not sealed again and the
+    /// senders' later transactions become executable. Sealing continues after the head.
+    async fn resume(&self) {
+        let chain = self.blockchain.lock().await;
+        let Some(head) = chain.head() else {
+            return;
+        };
+        let mut mempool = self.ingress.mempool().lock().await;
+        for number in 0..=head.number() {
+            match chain.block(number) {
+                Ok(Some(block)) => mempool.remove_included(&block.transactions),
+                Ok(None) => {}
+                Err(e) => warn!("Failed to read block {}: {}", number, e),
+            }
+        }
+        drop(mempool);
+        drop(chain);
+        if let Err(e) = self.ingress.compact().await {
+            warn!("Failed to compact the journal: {}", e);
+        }
+        info!("Resuming after block {}", head.number());
+    }
+
+    /// Proposes a block if the next block falls in this sequencer's slot and is either
+    /// requested or due under the sealing mode. Otherwise, if the chain has not moved on
+    /// since the last tick, re-gossips this node's messages for the current round in case
+    /// other nodes missed them, and passes the slot on once the proposer has timed out.
+    async fn tick(&mut self, stalled: bool, requested: bool) {
+        let number = self.blockchain.lock().await.height();
+        if stalled && self.is_due().await && self.time_out(number) {
+            // Vote on a proposal already received for the new view.
+            self.step().await;
+        }
+        let proposed = self
+            .rounds
+            .get(&number)
+            .is_some_and(|round| round.proposal.is_some());
+        if !proposed && self.is_proposer().await && (requested || self.is_due().await) {
             let block = self.seal().await;
-            info!("Sealed block: {:?}", block);
+            info!("Proposed block: {} {}", block.number(), block.hash());
+            return;
+        }
+        if !stalled {
+            return;
+        }
+        let mut messages = self
+            .rounds
+            .get(&number)
+            .map(|round| round.sent.clone())
+            .unwrap_or_default();
+        if let Some(head) = self.head().await {
+            if head.header().sequencer == self.signer_at(head.number()).address {
+                messages.push(encode(&head, BLOCKS_TOPIC));
+            }
+        }
+        for msg in messages {
+            self.outbound.send(msg).await.unwrap();
+        }
+    }
+
+    /// Preconfirms the pooled transactions not yet preconfirmed for the upcoming block, if it
+    /// falls in this sequencer's slot and has not been proposed yet. Each preconfirmation lists
+    /// all transactions preconfirmed for the block so far, which the block will begin with.
+    async fn preconfirm(&mut self) {
+        let number = self.blockchain.lock().await.height();
+        let proposed = self
+            .rounds
+            .get(&number)
+            .is_some_and(|round| round.proposal.is_some());
+        if proposed || !self.is_proposer().await {
+            return;
+        }
+        let pending = self.preconfirmable().await;
+        if pending.is_empty() {
+            return;
+        }
+        self.preconfirmed.extend(pending);
+        self.issue_preconfirmation(number).await;
+    }
+
+    /// Signs a preconfirmation listing all transactions preconfirmed for the upcoming block so
+    /// far, and gossips it to the other nodes and the subscribers.
+    async fn issue_preconfirmation(&mut self, number: u64) {
+        let index = match &self.preconfirmation {
+            Some(last) if last.sub_block().number == number => last.sub_block().index + 1,
+            _ => 0,
+        };
+        let signer = self.signer_at(number);
+        let sub_block = SubBlock {
+            sequencer: signer.address,
+            number,
+            index,
+            transactions: self
+                .preconfirmed
+                .iter()
+                .map(|tx| tx.transaction.hash())
+                .collect(),
+        };
+        let preconfirmation = SignedSubBlock::new(sub_block, signer);
+        debug!(
+            "Preconfirmed {} transactions for block {}",
+            self.preconfirmed.len(),
+            number
+        );
+        self.outbound
+            .send(encode(&preconfirmation, PRECONFIRMATIONS_TOPIC))
+            .await
+            .unwrap();
+        // There may be no subscribers.
+        let _ = self.preconfirmations.send(preconfirmation.clone());
+        self.preconfirmation = Some(preconfirmation);
+    }
+
+    /// Returns the pending transactions that can be preconfirmed next, in priority order.
+    /// Transactions expiring at a time are left for sealing, as whether they are still valid
+    /// depends on the block time, and so are later transactions from their senders.
+    async fn preconfirmable(&self) -> Vec<SignedTransaction> {
+        let mut blocked = HashSet::new();
+        let mut preconfirmable = vec![];
+        for tx in self.ingress.mempool().lock().await.pending() {
+            let sender = tx.transaction.sender();
+            if matches!(tx.transaction.expiry(), Some(Expiry::Time(_))) {
+                blocked.insert(sender);
+            } else if !blocked.contains(&sender) && !self.preconfirmed.contains(&tx) {
+                preconfirmable.push(tx);
+            }
+        }
+        preconfirmable
+    }
+
+    /// Counts a block period in which a block was due but the chain did not move on, and
+    /// moves to the next view once [VIEW_TIMEOUT] periods have passed, passing the slot on to
+    /// the next sequencer in turn. A node that precommitted to a block stays in its view, so
+    /// that no two blocks can be finalized at the same number. Returns whether the view changed.
+    fn time_out(&mut self, number: u64) -> bool {
+        let round = self.rounds.entry(number).or_default();
+        if round.precommitted {
+            return false;
+        }
+        round.stalls += 1;
+        if round.stalls < VIEW_TIMEOUT {
+            return false;
+        }
+        round.stalls = 0;
+        round.view += 1;
+        let view = round.view;
+        if round
+            .proposal
+            .as_ref()
+            .is_some_and(|proposal| proposal.header().view < view)
+        {
+            round.proposal = None;
+        }
+        round.preconfirmation = None;
+        round.prevoted = false;
+        round.sent.clear();
+        warn!(
+            "Timed out waiting for block {}, moving to view {}",
+            number, view
+        );
+        true
+    }
+
+    /// Returns the view this node is in for the block with the given number.
+    fn view(&self, number: u64) -> u64 {
+        self.rounds.get(&number).map_or(0, |round| round.view)
+    }
+
+    /// Returns whether a block is due under the sealing mode, unless explicitly requested.
+    async fn is_due(&self) -> bool {
+        let pending = !self.ingress.mempool().lock().await.pending().is_empty();
+        match self.mode {
+            SealingMode::Interval => true,
+            SealingMode::Instant => pending,
+            SealingMode::Manual => false,
+            SealingMode::SkipEmpty { max_idle } => {
+                let idle = self.head().await.is_none_or(|head| {
+                    let elapsed = self.clock.now().saturating_sub(head.header().timestamp);
+                    u128::from(elapsed) >= max_idle.as_millis()
+                });
+                pending || idle
+            }
+        }
+    }
+
+    /// Handles a message gossiped by another node.
+    async fn handle(&mut self, msg: GossipMessage) {
+        match msg.topic.as_str() {
+            TRANSACTIONS_TOPIC => match serde_json::from_slice(&msg.data) {
+                // Invalid transactions are dropped, while conflicts are expected as the same
+                // transaction is gossiped by several nodes.
+                Ok(transaction) => match self.add_transaction(transaction).await {
+                    Ok(()) => {}
+                    Err(e) if e.is_conflict() => debug!("Gossiped transaction not pooled: {}", e),
+                    Err(e) => warn!("Dropped invalid gossiped transaction: {}", e),
+                },
+                Err(e) => warn!("Failed to decode transaction: {}", e),
+            },
+            PRECONFIRMATIONS_TOPIC => match serde_json::from_slice(&msg.data) {
+                Ok(preconfirmation) => self.on_preconfirmation(preconfirmation).await,
+                Err(e) => warn!("Failed to decode preconfirmation: {}", e),
+            },
+            PROPOSALS_TOPIC => match serde_json::from_slice(&msg.data) {
+                Ok(block) => self.on_proposal(block).await,
+                Err(e) => warn!("Failed to decode proposal: {}", e),
+            },
+            VOTES_TOPIC => match serde_json::from_slice(&msg.data) {
+                Ok(vote) => self.on_vote(vote).await,
+                Err(e) => warn!("Failed to decode vote: {}", e),
+            },
+            BLOCKS_TOPIC => match serde_json::from_slice(&msg.data) {
+                Ok(block) => self.on_block(block).await,
+                Err(e) => warn!("Failed to decode block: {}", e),
+            },
+            EVIDENCE_TOPIC => match serde_json::from_slice::<EquivocationEvidence>(&msg.data) {
+                Ok(evidence) => match self.evidence.lock().await.add(evidence.clone()) {
+                    Ok(true) => warn!(
+                        "Received evidence of sequencer {} equivocating at block {}",
+                        evidence.offender(),
+                        evidence.number()
+                    ),
+                    Ok(false) => {}
+                    Err(e) => warn!("Failed to persist evidence: {}", e),
+                },
+                Err(e) => warn!("Failed to decode evidence: {}", e),
+            },
+            _ => {}
+        }
+    }
+
+    /// Records a proposed block in its voting round. The first proposal for the latest view
+    /// is kept, unless this node has already precommitted to a block. Proposals for a later
+    /// view than this node's wait for it to time out into that view.
+    async fn on_proposal(&mut self, block: Block) {
+        self.detect(&block).await;
+        if block.number() < self.blockchain.lock().await.height() {
+            return;
+        }
+        let round = self.rounds.entry(block.number()).or_default();
+        let view = block.header().view;
+        if view >= round.view
+            && !round.precommitted
+            && round
+                .proposal
+                .as_ref()
+                .is_none_or(|proposal| proposal.header().view < view)
+        {
+            round.proposal = Some(block);
+        }
+        self.step().await;
+    }
+
+    /// Records the latest preconfirmation issued by the sequencer in the slot of the upcoming
+    /// block in the current view, which its proposal must be consistent with.
+    async fn on_preconfirmation(&mut self, preconfirmation: SignedSubBlock) {
+        let sub_block = preconfirmation.sub_block();
+        let number = sub_block.number;
+        let view = self.view(number);
+        {
+            let chain = self.blockchain.lock().await;
+            if number < chain.height() {
+                return;
+            }
+            if !preconfirmation.verify() || chain.sequencer(number, view) != sub_block.sequencer {
+                warn!("Rejected preconfirmation for block {}", number);
+                return;
+            }
+        }
+        let round = self.rounds.entry(number).or_default();
+        if round
+            .preconfirmation
+            .as_ref()
+            .is_none_or(|latest| latest.sub_block().index < sub_block.index)
+        {
+            round.preconfirmation = Some(preconfirmation);
+        }
+    }
+
+    /// Records a vote cast by a committee member in its voting round.
+    async fn on_vote(&mut self, vote: SignedVote) {
+        let number = vote.vote().number;
+        let voter = {
+            let chain = self.blockchain.lock().await;
+            if number < chain.height() {
+                return;
+            }
+            match vote.voter() {
+                Some(voter) if chain.sequencers(number).contains(&voter) => voter,
+                _ => {
+                    warn!(
+                        "Rejected vote for block {} from outside the committee",
+                        number
+                    );
+                    return;
+                }
+            }
+        };
+        let round = self.rounds.entry(number).or_default();
+        match vote.vote().kind {
+            VoteKind::Prevote => round.prevotes.add(voter, vote),
+            VoteKind::Precommit => round.precommits.add(voter, vote),
+        }
+        self.step().await;
+    }
+
+    /// Imports a finalized block gossiped by another node, catching up on a round this node
+    /// did not conclude itself.
+    async fn on_block(&mut self, block: Block) {
+        self.detect(&block).await;
+        if block.number() != self.blockchain.lock().await.height() {
+            debug!(
+                "Ignoring block {} not at the head of the chain",
+                block.number()
+            );
+            return;
+        }
+        if self.finalize(block).await {
+            self.step().await;
+        }
+    }
+
+    /// Checks a gossiped block against the canonical chain and the proposal recorded for its
+    /// round, reporting any equivocation by the sequencer that sealed it.
+    async fn detect(&self, block: &Block) {
+        let mut evidence = match self.blockchain.lock().await.equivocation(block.signed()) {
+            Ok(evidence) => evidence,
+            Err(e) => {
+                warn!("Failed to read header {}: {}", block.number(), e);
+                None
+            }
+        };
+        if let Some(proposal) = self
+            .rounds
+            .get(&block.number())
+            .and_then(|round| round.proposal.as_ref())
+        {
+            evidence = evidence.or_else(|| {
+                EquivocationEvidence::new(proposal.signed().clone(), block.signed().clone())
+            });
+        }
+        if let Some(evidence) = evidence {
+            self.report(evidence).await;
         }
     }
 
-    /// Adds a transaction to the pool to be included in the next block.
-    pub async fn add_transaction(&mut self, transaction: SignedTransaction) {
-        match &transaction.transaction {
-            Transaction::Withdrawal(tx) => {
-                self.blockchain.lock().await.withdraw(tx);
-                self.withdrawals_pool.push(transaction);
+    /// Persists new evidence of equivocation and gossips it to the other nodes.
+    async fn report(&self, evidence: EquivocationEvidence) {
+        match self.evidence.lock().await.add(evidence.clone()) {
+            Ok(true) => {
+                warn!(
+                    "Sequencer {} equivocated at block {}",
+                    evidence.offender(),
+                    evidence.number()
+                );
+                self.outbound
+                    .send(encode(&evidence, EVIDENCE_TOPIC))
+                    .await
+                    .unwrap();
             }
-            Transaction::Dynamic(tx) => {
-                self.blockchain.lock().await.transact(tx);
-                self.transactions_pool.lock().await.push(transaction);
+            Ok(false) => {}
+            Err(e) => warn!("Failed to persist evidence: {}", e),
+        }
+    }
+
+    /// Advances the voting round for the head of the chain as far as the collected votes
+    /// allow, finalizing blocks as their rounds conclude.
+    async fn step(&mut self) {
+        loop {
+            let (number, committee) = {
+                let chain = self.blockchain.lock().await;
+                let number = chain.height();
+                let Some(round) = self.rounds.get_mut(&number) else {
+                    return;
+                };
+                let Some(proposal) = &round.proposal else {
+                    return;
+                };
+                if proposal.header().view > round.view {
+                    return;
+                }
+                if let Err(e) = chain.validate(proposal) {
+                    warn!("Rejected proposal {}: {}", number, e);
+                    round.proposal = None;
+                    return;
+                }
+                if let Some(preconfirmation) = &round.preconfirmation {
+                    if !preconfirmation.is_consistent(proposal) {
+                        warn!("Rejected proposal {}: breaks preconfirmation", number);
+                        round.proposal = None;
+                        return;
+                    }
+                }
+                (number, chain.sequencers(number).to_vec())
+            };
+            let mut round = self.rounds.remove(&number).unwrap();
+            let block_hash = round.proposal.as_ref().unwrap().hash();
+            let signer = self.signer_at(number);
+            let member = committee.contains(&signer.address);
+
+            // Prevote for a valid proposal, and precommit once a quorum has prevoted.
+            let mut votes = vec![];
+            if member && !round.prevoted {
+                let vote = self.vote(VoteKind::Prevote, number, block_hash);
+                round.prevotes.add(signer.address, vote.clone());
+                round.prevoted = true;
+                votes.push(vote);
+            }
+            if member
+                && !round.precommitted
+                && round.prevotes.count(block_hash) >= quorum(committee.len())
+            {
+                let vote = self.vote(VoteKind::Precommit, number, block_hash);
+                round.precommits.add(signer.address, vote.clone());
+                round.precommitted = true;
+                votes.push(vote);
             }
+            for vote in votes {
+                let msg = encode(&vote, VOTES_TOPIC);
+                round.sent.push(msg.clone());
+                self.outbound.send(msg).await.unwrap();
+            }
+
+            // Finalize the block once a quorum has precommitted.
+            if round.precommits.count(block_hash) < quorum(committee.len()) {
+                self.rounds.insert(number, round);
+                return;
+            }
+            let certificate = CommitCertificate::new(round.precommits.votes(block_hash));
+            let block = round.proposal.take().unwrap().with_certificate(certificate);
+            if !self.finalize(block).await {
+                return;
+            }
+        }
+    }
+
+    /// Signs a vote for the given block.
+    fn vote(&self, kind: VoteKind, number: u64, block_hash: B256) -> SignedVote {
+        let vote = Vote {
+            kind,
+            number,
+            block_hash,
+        };
+        SignedVote::new(vote, self.signer_at(number))
+    }
+
+    /// Imports a finalized block, dropping its transactions and those it expires from the
+    /// mempool, along with the voting rounds and preconfirmations it concludes. Blocks sealed by this sequencer are gossiped to nodes following
+    /// the chain. Returns whether the block was imported.
+    async fn finalize(&mut self, block: Block) -> bool {
+        let number = block.number();
+        if let Err(e) = self.blockchain.lock().await.import(block.clone()) {
+            warn!("Rejected block {}: {}", number, e);
+            return false;
+        }
+        info!("Finalized block: {:?}", block);
+        let mut mempool = self.ingress.mempool().lock().await;
+        mempool.remove_included(&block.transactions);
+        for hash in mempool.sweep(number, block.header().timestamp) {
+            info!("Dropped expired transaction: {}", hash);
+        }
+        drop(mempool);
+        if let Err(e) = self.ingress.compact().await {
+            warn!("Failed to compact the journal: {}", e);
+        }
+        self.rounds = self.rounds.split_off(&(number + 1));
+        self.preconfirmed.clear();
+        if block.header().sequencer == self.signer_at(number).address {
+            self.outbound
+                .send(encode(&block, BLOCKS_TOPIC))
+                .await
+                .unwrap();
+        }
+        // There may be no subscribers.
+        let _ = self.finalized.send(block);
+        true
+    }
+
+    /// Returns the signer to seal the block with the given number, accounting for
+    /// a recorded handover that has taken effect.
+    fn signer_at(&self, number: u64) -> &Signer {
+        match &self.next {
+            Some(next) if next.effective_at <= number => &next.next,
+            _ => &self.signer,
+        }
+    }
+
+    /// Returns whether the next block falls in this sequencer's slot in the current view.
+    pub async fn is_proposer(&self) -> bool {
+        let chain = self.blockchain.lock().await;
+        let number = chain.height();
+        chain.sequencer(number, self.view(number)) == self.signer_at(number).address
+    }
+
+    /// Admits a transaction to the mempool to be included in an upcoming block. A preconfirmed
+    /// transaction cannot be replaced, as the sequencer has committed to including it.
+    /// The Merkle trees are updated once the block including it is finalized.
+    pub async fn add_transaction(
+        &mut self,
+        transaction: SignedTransaction,
+    ) -> Result<(), AdmissionError> {
+        let sender = transaction.transaction.sender();
+        let nonce = transaction.transaction.nonce();
+        let preconfirmed = self.preconfirmed.iter().any(|tx| {
+            tx.transaction.sender() == sender
+                && tx.transaction.nonce() == nonce
+                && *tx != transaction
+        });
+        if preconfirmed {
+            return Err(AdmissionError::Preconfirmed(nonce));
+        }
+        self.ingress.admit(transaction).await
+    }
+
+    /// Adds a transaction submitted directly to this sequencer to the mempool. If the upcoming
+    /// block falls in this sequencer's slot and has not been proposed yet, an executable
+    /// transaction is preconfirmed for it, after any earlier transactions from its sender, and
+    /// a signed receipt promising its position is returned. Resubmitting a preconfirmed
+    /// transaction returns its receipt again. Errors if the transaction is not admitted to the
+    /// mempool.
+    pub async fn submit(
+        &mut self,
+        transaction: SignedTransaction,
+    ) -> Result<Option<SignedSoftReceipt>, AdmissionError> {
+        let number = self.blockchain.lock().await.height();
+        let proposed = self
+            .rounds
+            .get(&number)
+            .is_some_and(|round| round.proposal.is_some());
+        let position = self.preconfirmed.iter().position(|tx| *tx == transaction);
+        if let Some(position) = position.filter(|_| !proposed) {
+            return Ok(Some(self.receipt(number, position, &transaction)));
+        }
+        self.add_transaction(transaction.clone()).await?;
+        if proposed || !self.is_proposer().await {
+            return Ok(None);
+        }
+        let sender = transaction.transaction.sender();
+        let nonce = transaction.transaction.nonce();
+        let preconfirmable = self.preconfirmable().await;
+        if !preconfirmable.contains(&transaction) {
+            return Ok(None);
         }
+        let earlier: Vec<SignedTransaction> = preconfirmable
+            .into_iter()
+            .filter(|tx| tx.transaction.sender() == sender && tx.transaction.nonce() <= nonce)
+            .collect();
+        self.preconfirmed.extend(earlier);
+        self.issue_preconfirmation(number).await;
+        let position = self.preconfirmed.len() - 1;
+        Ok(Some(self.receipt(number, position, &transaction)))
     }
 
-    /// Creates the latest canonical block and signs.
-    /// Transaction pools are cleared during this process.
+    /// Signs a receipt promising the transaction's position in the given upcoming block.
+    fn receipt(
+        &self,
+        number: u64,
+        position: usize,
+        transaction: &SignedTransaction,
+    ) -> SignedSoftReceipt {
+        let signer = self.signer_at(number);
+        let receipt = SoftReceipt {
+            sequencer: signer.address,
+            tx_hash: transaction.transaction.hash(),
+            number,
+            position: position as u64,
+        };
+        SignedSoftReceipt::new(receipt, signer)
+    }
+
+    /// Creates the next block from the pooled transactions, signs it and proposes it to the
+    /// sequencer committee. Pooled transactions are dropped once the block is finalized,
+    /// which happens immediately when this sequencer alone forms a quorum.
     pub async fn seal(&mut self) -> Block {
-        // Record the time the latest block time.
-        let block_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let chain = self.blockchain.lock().await;
+        let number = chain.height();
+
+        // Record the block time, strictly after the parent block even if the clock lags behind.
+        let block_time = match chain.head() {
+            Some(parent) => self.clock.now().max(parent.header().timestamp + 1),
+            None => self.clock.now(),
+        };
+
+        // Switch to the next signer once its handover takes effect.
+        if self.next.as_ref().is_some_and(|n| n.effective_at <= number) {
+            let next = self.next.take().unwrap();
+            info!(
+                "Handing over to sequencer {} at block {}",
+                next.next.address, number
+            );
+            self.signer = next.next;
+        }
+
+        // Record a requested handover once any previous one has taken effect.
+        let mut handover = None;
+        if self.next.is_none() {
+            if let Some(rotation) = self.rotation.lock().await.take() {
+                let effective_at = rotation.effective_at.max(number + 1);
+                let signed = SignedHandover::new(
+                    Handover {
+                        current: self.signer.address,
+                        next: rotation.next.address,
+                        effective_at,
+                    },
+                    &self.signer,
+                );
+                handover = Some(signed);
+                self.next = Some(Rotation {
+                    next: rotation.next,
+                    effective_at,
+                });
+            }
+        }
+
+        // Drop the transactions that have expired by this block.
+        let mut mempool = self.ingress.mempool().lock().await;
+        for hash in mempool.sweep(number, block_time) {
+            info!("Dropped expired transaction: {}", hash);
+        }
+
+        // Collect the preconfirmed transactions followed by the remaining executable
+        // transactions in priority order, and compute the state roots after applying them.
+        let mut transactions = self.preconfirmed.clone();
+        for tx in mempool.pending() {
+            if !transactions.contains(&tx) {
+                transactions.push(tx);
+            }
+        }
+        drop(mempool);
+        let roots = chain.execute(&transactions).roots();
 
         // Construct the block header.
-        let mut chain = self.blockchain.lock().await;
         let header = BlockHeader {
             sequencer: self.signer.address,
-            number: chain.height(),
+            number,
+            view: self.view(number),
             timestamp: block_time,
             parent_digest: chain.head().map(|b| b.hash()),
-            withdrawals_root: format!("{:x}", chain.withdrawals_tree.root()),
-            transactions_root: format!("{:x}", chain.transactions_tree.root()),
+            withdrawals_root: roots.withdrawals_root,
+            transactions_root: roots.transactions_root,
+            handover,
         };
+        drop(chain);
 
-        // Drain the transaction pools and construct the block.
-        let block = Block::new(
-            SignedBlockHeader::new(header, &self.signer),
-            self.transactions_pool
-                .lock()
-                .await
-                .drain(..)
-                .chain(self.withdrawals_pool.drain(..))
-                .collect(),
-        );
-        chain.push(block.clone());
+        // Construct the block from the collected transactions.
+        let block = Block::new(SignedBlockHeader::new(header, &self.signer), transactions);
+
+        // Propose the block and vote on it.
+        let msg = encode(&block, PROPOSALS_TOPIC);
+        let round = self.rounds.entry(number).or_default();
+        round.proposal = Some(block.clone());
+        round.sent.push(msg.clone());
+        self.outbound.send(msg).await.unwrap();
+        self.step().await;
         block
     }
 
@@ -157,3 +1010,445 @@ impl Sequencer {
         self.blockchain.lock().await.head()
     }
 }
+
+/// Encodes a value as a gossip message on the given topic.
+fn encode(value: &impl Serialize, topic: &str) -> (Vec<u8>, String) {
+    (
+        json!(value).to_string().as_bytes().to_vec(),
+        topic.to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DiskStorage, ManualClock, Transaction, TransactionStatus};
+
+    /// The ends of a test sequencer's channels, kept open for the duration of a test.
+    type Channels = (Sender<GossipMessage>, Receiver<(Vec<u8>, String)>);
+
+    /// Creates a sole sequencer sealing in the given mode and timestamping blocks with
+    /// the given clock.
+    fn sequencer(signer: &Signer, clock: &ManualClock, mode: SealingMode) -> (Sequencer, Channels) {
+        sequencer_on(Blockchain::new(vec![signer.address]), signer, clock, mode)
+    }
+
+    /// Creates a sequencer as above, extending the given chain.
+    fn sequencer_on(
+        chain: Blockchain,
+        signer: &Signer,
+        clock: &ManualClock,
+        mode: SealingMode,
+    ) -> (Sequencer, Channels) {
+        let chain = Arc::new(Mutex::new(chain));
+        let (tx_in, rx_in) = tokio::sync::mpsc::channel(32);
+        let (tx_out, rx_out) = tokio::sync::mpsc::channel(32);
+        let evidence = Arc::new(Mutex::new(EvidenceStore::default()));
+        let sequencer = Sequencer::new(
+            signer.clone(),
+            Arc::new(Mutex::new(Mempool::default())),
+            chain,
+            rx_in,
+            tx_out,
+            evidence,
+        )
+        .with_clock(clock.clone())
+        .with_mode(mode);
+        (sequencer, (tx_in, rx_out))
+    }
+
+    #[tokio::test]
+    async fn test_seal_with_manual_clock() {
+        let signer = Signer::random();
+        let clock = ManualClock::new(100);
+        let (mut sequencer, _channels) = sequencer(&signer, &clock, SealingMode::Interval);
+
+        // Blocks are timestamped by the clock.
+        assert_eq!(sequencer.seal().await.header().timestamp, 100);
+
+        // Timestamps strictly increase while the clock stands still or jumps backwards.
+        assert_eq!(sequencer.seal().await.header().timestamp, 101);
+        clock.set(50);
+        assert_eq!(sequencer.seal().await.header().timestamp, 102);
+
+        // Timestamps follow the clock again once it has caught up.
+        clock.advance(100);
+        assert_eq!(sequencer.seal().await.header().timestamp, 150);
+        assert_eq!(sequencer.blockchain.lock().await.height(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_rotate() {
+        let signer = Signer::random();
+        let next = Signer::random();
+        let clock = ManualClock::new(100);
+        let (sequencer, _channels) = sequencer(&signer, &clock, SealingMode::Manual);
+        let mut sequencer = sequencer.with_next_key(next.clone());
+        let rotator = sequencer.rotator();
+        let handover = |signer: &Signer, next: Address| {
+            let handover = Handover {
+                current: signer.address,
+                next,
+                effective_at: 2,
+            };
+            SignedHandover::new(handover, signer)
+        };
+
+        // Handovers must be signed by the current key and name the key held by the sequencer.
+        let stranger = Signer::random();
+        assert_eq!(
+            rotator.rotate(&handover(&stranger, next.address)).await,
+            Err(HandoverError::InvalidSignature)
+        );
+        assert_eq!(
+            rotator.rotate(&handover(&signer, stranger.address)).await,
+            Err(HandoverError::UnknownKey(stranger.address))
+        );
+
+        // An accepted handover is recorded in the next block, after which the next key seals.
+        rotator
+            .rotate(&handover(&signer, next.address))
+            .await
+            .unwrap();
+        assert!(sequencer.seal().await.header().handover.is_some());
+        assert_eq!(sequencer.seal().await.header().sequencer, signer.address);
+        assert_eq!(sequencer.seal().await.header().sequencer, next.address);
+    }
+
+    #[tokio::test]
+    async fn test_restart() {
+        let signer = Signer::random();
+        let clock = ManualClock::new(100);
+        let datadir = std::env::temp_dir().join(format!("chain-{}", signer.address));
+        let chain = || {
+            Blockchain::new(vec![signer.address])
+                .with_storage(DiskStorage::open(&datadir).unwrap())
+                .unwrap()
+        };
+        let transaction =
+            |nonce| SignedTransaction::new(Transaction::dynamic(signer.address, 1, nonce), &signer);
+
+        // Blocks sealed by the sequencer are persisted.
+        let (mut sequencer, channels) = sequencer_on(chain(), &signer, &clock, SealingMode::Manual);
+        sequencer.add_transaction(transaction(0)).await.unwrap();
+        sequencer.seal().await;
+        let head = sequencer.seal().await;
+        drop((sequencer, channels));
+
+        // After a restart, sealing continues after the persisted head, and transactions
+        // already included are not admitted again.
+        let (mut sequencer, _channels) =
+            sequencer_on(chain(), &signer, &clock, SealingMode::Manual);
+        sequencer.resume().await;
+        let error = sequencer.add_transaction(transaction(0)).await.unwrap_err();
+        assert_eq!(error.code(), "nonce_too_low");
+        let next = transaction(1);
+        sequencer.add_transaction(next.clone()).await.unwrap();
+        let block = sequencer.seal().await;
+        assert_eq!(block.number(), 2);
+        assert_eq!(block.header().parent_digest, Some(head.hash()));
+        assert_eq!(block.transactions, vec![next]);
+        std::fs::remove_dir_all(datadir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sealing_modes() {
+        let signer = Signer::random();
+        let clock = ManualClock::new(100);
+        let transaction =
+            SignedTransaction::new(Transaction::dynamic(signer.address, 1, 0), &signer);
+
+        // Manual sealing only seals on request.
+        let (mut manual, _channels) = sequencer(&signer, &clock, SealingMode::Manual);
+        manual.add_transaction(transaction.clone()).await.unwrap();
+        manual.tick(false, false).await;
+        assert!(manual.head().await.is_none());
+        manual.tick(false, true).await;
+        assert_eq!(manual.head().await.unwrap().transactions.len(), 1);
+
+        // Instant sealing only seals when there are pending transactions.
+        let (mut instant, _channels) = sequencer(&signer, &clock, SealingMode::Instant);
+        let mut preconfirmations = instant.preconfirmations().subscribe();
+        instant.tick(false, false).await;
+        assert!(instant.head().await.is_none());
+        let receipt = instant.submit(transaction.clone()).await.unwrap().unwrap();
+        assert_eq!(
+            (receipt.receipt().number, receipt.receipt().position),
+            (0, 0)
+        );
+        let resubmitted = instant.submit(transaction.clone()).await.unwrap();
+        assert_eq!(resubmitted, Some(receipt.clone()));
+
+        // The receipted transaction is preconfirmed at once.
+        let preconfirmation = preconfirmations.try_recv().unwrap();
+        assert_eq!(
+            preconfirmation.sub_block().transactions,
+            vec![transaction.transaction.hash()]
+        );
+        instant.tick(false, false).await;
+        assert_eq!(instant.head().await.unwrap().transactions.len(), 1);
+
+        // The block honours the receipt issued on submission.
+        assert!(receipt.verify());
+        let chain = instant.blockchain.lock().await;
+        assert_eq!(chain.honours(&receipt), Ok(Some(true)));
+
+        // Skip-empty sealing seals empty blocks only once the chain has been idle for long enough.
+        let max_idle = Duration::from_millis(10);
+        let mode = SealingMode::SkipEmpty { max_idle };
+        let (mut sequencer, _channels) = sequencer(&signer, &clock, mode);
+        sequencer.tick(false, false).await;
+        assert_eq!(sequencer.head().await.unwrap().number(), 0);
+        clock.advance(5);
+        sequencer.tick(false, false).await;
+        assert_eq!(sequencer.head().await.unwrap().number(), 0);
+        sequencer.add_transaction(transaction).await.unwrap();
+        sequencer.tick(false, false).await;
+        assert_eq!(sequencer.head().await.unwrap().number(), 1);
+        clock.advance(9);
+        sequencer.tick(false, false).await;
+        assert_eq!(sequencer.head().await.unwrap().number(), 1);
+        clock.advance(1);
+        sequencer.tick(false, false).await;
+        assert_eq!(sequencer.head().await.unwrap().number(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expiry() {
+        let signer = Signer::random();
+        let clock = ManualClock::new(100);
+        let (mut sequencer, _channels) = sequencer(&signer, &clock, SealingMode::Manual);
+        let expiring = |nonce, expiry| {
+            let tx = Transaction::dynamic(signer.address, 1, nonce).with_expiry(expiry);
+            SignedTransaction::new(tx, &signer)
+        };
+
+        // Expired transactions are not admitted.
+        assert_eq!(
+            sequencer
+                .add_transaction(expiring(0, Expiry::Time(99)))
+                .await,
+            Err(AdmissionError::Expired)
+        );
+
+        // Transactions expiring at a time are not preconfirmed, as the block time is unknown.
+        let by_block = expiring(0, Expiry::Block(0));
+        let by_time = expiring(1, Expiry::Time(150));
+        sequencer.submit(by_block.clone()).await.unwrap().unwrap();
+        assert_eq!(sequencer.submit(by_time.clone()).await, Ok(None));
+
+        // Transactions that expire before the block is sealed are dropped from it.
+        clock.advance(100);
+        let block = sequencer.seal().await;
+        assert_eq!(block.transactions, vec![by_block]);
+        let status = sequencer
+            .ingress
+            .mempool()
+            .lock()
+            .await
+            .status(&by_time.transaction.hash());
+        assert_eq!(status, Some(TransactionStatus::Expired));
+        assert_eq!(sequencer.head().await.map(|b| b.hash()), Some(block.hash()));
+
+        // Sequencers following a block drop the transactions it expires as well.
+        let other = Signer::random();
+        let (mut follower, _channels) = sequencer_on(
+            Blockchain::new(vec![signer.address]),
+            &other,
+            &clock,
+            SealingMode::Manual,
+        );
+        let tx = Transaction::dynamic(other.address, 1, 0).with_expiry(Expiry::Time(250));
+        let tx = SignedTransaction::new(tx, &other);
+        follower.add_transaction(tx.clone()).await.unwrap();
+        clock.advance(100);
+        let (mut proposer, _channels) = sequencer_on(
+            Blockchain::new(vec![signer.address]),
+            &signer,
+            &clock,
+            SealingMode::Manual,
+        );
+        proposer.seal().await;
+        follower.on_block(proposer.head().await.unwrap()).await;
+        let status = follower
+            .ingress
+            .mempool()
+            .lock()
+            .await
+            .status(&tx.transaction.hash());
+        assert_eq!(status, Some(TransactionStatus::Expired));
+    }
+
+    #[tokio::test]
+    async fn test_preconfirmations() {
+        let signer = Signer::random();
+        let clock = ManualClock::new(100);
+        let (mut sequencer, _channels) = sequencer(&signer, &clock, SealingMode::Manual);
+        let mut preconfirmations = sequencer.preconfirmations().subscribe();
+        let withdrawal =
+            SignedTransaction::new(Transaction::withdrawal(signer.address, 1, 0, 1), &signer);
+        let deposit = SignedTransaction::new(Transaction::dynamic(signer.address, 1, 1), &signer);
+
+        // Nothing is preconfirmed while the mempool is empty.
+        sequencer.preconfirm().await;
+        assert!(preconfirmations.try_recv().is_err());
+
+        // Each preconfirmation extends the previous one.
+        sequencer.add_transaction(withdrawal.clone()).await.unwrap();
+        sequencer.preconfirm().await;
+        let first = preconfirmations.try_recv().unwrap();
+        assert_eq!(
+            first.sub_block().transactions,
+            vec![withdrawal.transaction.hash()]
+        );
+        sequencer.add_transaction(deposit.clone()).await.unwrap();
+        sequencer.preconfirm().await;
+        let second = preconfirmations.try_recv().unwrap();
+        assert_eq!(second.sub_block().index, 1);
+        assert_eq!(second.sub_block().transactions.len(), 2);
+
+        // Preconfirmed transactions cannot be replaced, however much the replacement pays.
+        let replacement = Transaction::dynamic(signer.address, 1, 1).with_fees(100, 100);
+        let replacement = SignedTransaction::new(replacement, &signer);
+        assert_eq!(
+            sequencer.add_transaction(replacement).await,
+            Err(AdmissionError::Preconfirmed(1))
+        );
+
+        // The sealed block honours all preconfirmations.
+        let block = sequencer.seal().await;
+        assert!(first.verify() && first.is_consistent(&block));
+        assert!(second.verify() && second.is_consistent(&block));
+        assert_eq!(sequencer.head().await.map(|b| b.hash()), Some(block.hash()));
+        assert!(sequencer.preconfirmed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_submit_to_stopped_sequencer() {
+        let signer = Signer::random();
+        let clock = ManualClock::new(0);
+        let (sequencer, _channels) = sequencer(&signer, &clock, SealingMode::Manual);
+        let submitter = sequencer.submitter();
+        drop(sequencer);
+
+        // Submissions to a sequencer that has stopped are turned away rather than panicking.
+        let transaction =
+            SignedTransaction::new(Transaction::dynamic(signer.address, 1, 0), &signer);
+        let error = submitter.submit(transaction).await.unwrap_err();
+        assert_eq!(error.code(), "unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_preconfirmations_survive_failed_round() {
+        let signer = Signer::random();
+        let other = Signer::random();
+        let clock = ManualClock::new(100);
+        let chain = Blockchain::new(vec![signer.address, other.address]);
+        let (mut sequencer, _channels) = sequencer_on(chain, &signer, &clock, SealingMode::Manual);
+        let transaction =
+            SignedTransaction::new(Transaction::dynamic(signer.address, 1, 0), &signer);
+        let receipt = sequencer
+            .submit(transaction.clone())
+            .await
+            .unwrap()
+            .unwrap();
+
+        // The proposal lacks a quorum without the other member's votes.
+        let proposal = sequencer.seal().await;
+        assert!(sequencer.head().await.is_none());
+        assert_eq!(proposal.transactions, vec![transaction.clone()]);
+
+        // Once the round fails, the block is re-proposed beginning with the same
+        // preconfirmations, ahead of any better paying transaction pooled since.
+        let rich = Transaction::dynamic(other.address, 1, 0).with_fees(100, 100);
+        let rich = SignedTransaction::new(rich, &other);
+        sequencer.add_transaction(rich.clone()).await.unwrap();
+        sequencer.rounds.clear();
+        let block = sequencer.seal().await;
+        assert_eq!(block.transactions, vec![transaction, rich]);
+        assert!(receipt.is_honoured(&block));
+        assert_eq!(
+            sequencer
+                .preconfirmation
+                .as_ref()
+                .map(|p| p.is_consistent(&block)),
+            Some(true)
+        );
+    }
+
+    /// Delivers the messages gossiped by each sequencer to the others until none are left.
+    async fn relay(nodes: &mut [(Sequencer, Channels)]) {
+        loop {
+            let mut delivered = false;
+            for from in 0..nodes.len() {
+                while let Ok((data, topic)) = nodes[from].1 .1.try_recv() {
+                    delivered = true;
+                    for (to, (node, _)) in nodes.iter_mut().enumerate() {
+                        if to != from {
+                            deliver(node, &data, &topic).await;
+                        }
+                    }
+                }
+            }
+            if !delivered {
+                return;
+            }
+        }
+    }
+
+    /// Hands a gossiped consensus message to a sequencer.
+    async fn deliver(node: &mut Sequencer, data: &[u8], topic: &str) {
+        match topic {
+            PROPOSALS_TOPIC => {
+                node.on_proposal(serde_json::from_slice(data).unwrap())
+                    .await
+            }
+            VOTES_TOPIC => node.on_vote(serde_json::from_slice(data).unwrap()).await,
+            BLOCKS_TOPIC => node.on_block(serde_json::from_slice(data).unwrap()).await,
+            _ => {}
+        }
+    }
+
+    #[tokio::test]
+    async fn test_view_change() {
+        let signers: Vec<Signer> = (0..4).map(|_| Signer::random()).collect();
+        let committee: Vec<Address> = signers.iter().map(|s| s.address).collect();
+        let clock = ManualClock::new(100);
+
+        // The first sequencer, scheduled to propose block 0, is offline.
+        let mut nodes: Vec<_> = signers[1..]
+            .iter()
+            .map(|signer| {
+                let chain = Blockchain::new(committee.clone());
+                sequencer_on(chain, signer, &clock, SealingMode::Interval)
+            })
+            .collect();
+
+        // Nothing is proposed until the proposer times out.
+        for _ in 0..VIEW_TIMEOUT - 1 {
+            nodes[0].0.tick(true, false).await;
+        }
+        assert!(nodes[0].0.rounds[&0].proposal.is_none());
+
+        // The next sequencer in turn then proposes in the next view, which the others only
+        // vote on once they have timed out too.
+        nodes[0].0.tick(true, false).await;
+        let proposal = nodes[0].0.rounds[&0].proposal.clone().unwrap();
+        assert_eq!(proposal.header().view, 1);
+        assert_eq!(proposal.header().sequencer, signers[1].address);
+        relay(&mut nodes).await;
+        assert!(!nodes[1].0.rounds[&0].prevoted);
+        for (node, _) in &mut nodes[1..] {
+            for _ in 0..VIEW_TIMEOUT {
+                node.tick(true, false).await;
+            }
+        }
+        relay(&mut nodes).await;
+
+        // The block is finalized by the online sequencers.
+        for (node, _) in &nodes {
+            let head = node.head().await.unwrap();
+            assert_eq!(head.hash(), proposal.hash());
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use alloy_primitives::bytes::BufMut;
-use alloy_primitives::U256;
-use secp256k1::ecdsa::{RecoverableSignature, RecoveryId, Signature as SecpSignature};
+use alloy_primitives::{B256, U256};
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
 use secp256k1::rand::rngs::OsRng;
 use secp256k1::{Message, Secp256k1};
 use secp256k1::{PublicKey, SecretKey};
@@ -20,28 +20,25 @@ pub struct Signature {
     pub v: i32,
 }
 
-/// Converts a [Signature] into a [SecpSignature].
-impl From<&Signature> for SecpSignature {
-    fn from(signature: &Signature) -> Self {
+impl Signature {
+    /// Recovers the address that produced this signature over the given digest.
+    /// Returns None if the signature is malformed.
+    pub(crate) fn recover(&self, digest: B256) -> Option<Address> {
         let mut buf = Vec::new();
-        buf.put_slice(&signature.r.to_be_bytes::<32>());
-        buf.put_slice(&signature.s.to_be_bytes::<32>());
-        SecpSignature::from_compact(&buf).unwrap()
-    }
-}
-
-/// Converts a [Signature] into a [RecoverableSignature].
-impl From<&Signature> for RecoverableSignature {
-    fn from(signature: &Signature) -> Self {
-        let mut buf = Vec::new();
-        buf.put_slice(&signature.r.to_be_bytes::<32>());
-        buf.put_slice(&signature.s.to_be_bytes::<32>());
-        RecoverableSignature::from_compact(&buf, RecoveryId::from_i32(signature.v).unwrap())
-            .unwrap()
+        buf.put_slice(&self.r.to_be_bytes::<32>());
+        buf.put_slice(&self.s.to_be_bytes::<32>());
+        let recovery_id = RecoveryId::from_i32(self.v).ok()?;
+        let signature = RecoverableSignature::from_compact(&buf, recovery_id).ok()?;
+        let secp = Secp256k1::new();
+        let pk = secp
+            .recover_ecdsa(&Message::from_digest(digest.into()), &signature)
+            .ok()?;
+        Some(Address::from(pk))
     }
 }
 
 /// An entity capable of signing messages using secp2561k.
+#[derive(Clone)]
 pub struct Signer {
     pub sk: SecretKey,
     pub pk: PublicKey,
@@ -51,10 +48,19 @@ pub struct Signer {
 /// Converts a string into a [Signer].
 impl From<&str> for Signer {
     fn from(s: &str) -> Self {
-        let sk = SecretKey::from_str(s).unwrap();
+        s.parse().unwrap()
+    }
+}
+
+/// Parses a hex-encoded secret key into a [Signer].
+impl FromStr for Signer {
+    type Err = secp256k1::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let sk = SecretKey::from_str(s)?;
         let pk = PublicKey::from_secret_key_global(&sk);
         let address = Address::from(pk);
-        Signer { sk, pk, address }
+        Ok(Signer { sk, pk, address })
     }
 }
 
@@ -90,10 +96,18 @@ mod tests {
         let signer = Signer::random();
         let digest = [0u8; 32];
         let signature = signer.sign(digest);
-        let secp = Secp256k1::new();
-        let msg = Message::from_digest(digest);
-        let pk = secp.recover_ecdsa(&msg, &(&signature).into()).unwrap();
-        let address = Address::from(pk);
-        assert_eq!(address, signer.address);
+        assert_eq!(signature.recover(digest.into()), Some(signer.address));
+
+        // Malformed signatures recover no address.
+        let invalid = Signature {
+            v: 7,
+            ..signature.clone()
+        };
+        assert_eq!(invalid.recover(digest.into()), None);
+        let invalid = Signature {
+            r: U256::ZERO,
+            ..signature
+        };
+        assert_eq!(invalid.recover(digest.into()), None);
     }
 }
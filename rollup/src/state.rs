@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Frontier, SignedTransaction, Transaction};
+
+/// The roots of the Merkle trees committing to a [State].
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct StateRoots {
+    /// The root digest of the withdrawals Merkle tree.
+    pub withdrawals_root: String,
+    /// The root digest of the transactions Merkle tree.
+    pub transactions_root: String,
+}
+
+/// The state of the rollup: the frontiers of the withdrawals and transactions Merkle trees.
+/// The state is small and cheap to clone however many transactions have been applied.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+pub struct State {
+    /// The frontier of the tree of withdrawal transactions applied so far.
+    pub withdrawals: Frontier,
+    /// The frontier of the tree of dynamic transactions applied so far.
+    pub transactions: Frontier,
+}
+
+impl State {
+    /// Applies transactions to the state by appending them to the respective tree.
+    /// This is the state transition function of the rollup.
+    pub fn apply(&mut self, transactions: &[SignedTransaction]) {
+        for tx in transactions {
+            match &tx.transaction {
+                Transaction::Withdrawal(tx) => self.withdrawals.push(tx.hash()),
+                Transaction::Dynamic(tx) => self.transactions.push(tx.hash()),
+            }
+        }
+    }
+
+    /// Computes the roots of the Merkle trees of the state.
+    pub fn roots(&self) -> StateRoots {
+        StateRoots {
+            withdrawals_root: format!("{:x}", self.withdrawals.root()),
+            transactions_root: format!("{:x}", self.transactions.root()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MerkleProof, Signer};
+
+    #[test]
+    fn test_state_apply() {
+        let signer = Signer::random();
+        let deposit = SignedTransaction::new(Transaction::dynamic(signer.address, 1, 0), &signer);
+        let withdrawal =
+            SignedTransaction::new(Transaction::withdrawal(signer.address, 1, 1, 2), &signer);
+
+        // Transactions are routed to their respective tree.
+        let mut state = State::default();
+        let empty = state.roots();
+        state.apply(&[deposit.clone(), withdrawal.clone()]);
+        assert_eq!(state.transactions.len(), 1);
+        assert_eq!(state.withdrawals.len(), 1);
+
+        // Both roots change, deterministically.
+        let roots = state.roots();
+        assert_ne!(roots.withdrawals_root, empty.withdrawals_root);
+        assert_ne!(roots.transactions_root, empty.transactions_root);
+        assert_eq!(roots, state.clone().roots());
+
+        // Withdrawals can be proven against the withdrawals root, but deposits cannot.
+        let proof = MerkleProof::new(&[withdrawal.transaction.hash()], 0).unwrap();
+        assert!(proof.verify(&roots.withdrawals_root));
+        let proof = MerkleProof::new(&[deposit.transaction.hash()], 0).unwrap();
+        assert!(!proof.verify(&roots.withdrawals_root));
+    }
+}
@@ -0,0 +1,319 @@
+use std::fs;
+use std::path::Path;
+
+use redb::{Database, ReadableTable, ReadableTableMetadata, TableDefinition};
+use serde::de::DeserializeOwned;
+
+use crate::{Block, Proof, SignedBlockHeader, State, StateRoots};
+
+/// The table of encoded blocks by number.
+const BLOCKS: TableDefinition<u64, &[u8]> = TableDefinition::new("blocks");
+/// The table of encoded signed block headers by number.
+const HEADERS: TableDefinition<u64, &[u8]> = TableDefinition::new("headers");
+/// The table of encoded validity proofs by the number of the block they prove.
+const PROOFS: TableDefinition<u64, &[u8]> = TableDefinition::new("proofs");
+/// The table holding the encoded state roots after the last stored block.
+const ROOTS: TableDefinition<&str, &[u8]> = TableDefinition::new("roots");
+/// The key of the state roots in their table.
+const HEAD: &str = "head";
+
+/// An error encountered while reading from or writing to [Storage].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageError {
+    /// The storage backend failed.
+    Backend(String),
+    /// A stored value could not be decoded.
+    Corrupt(String),
+    /// The block does not follow the last stored block.
+    UnexpectedNumber { expected: u64, actual: u64 },
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::Backend(e) => write!(f, "storage backend failed: {e}"),
+            StorageError::Corrupt(e) => write!(f, "corrupt storage: {e}"),
+            StorageError::UnexpectedNumber { expected, actual } => {
+                write!(f, "unexpected block number {actual}, expected {expected}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+/// Persists the blocks of a [Blockchain](crate::Blockchain), their headers, the roots of
+/// its Merkle trees and the validity proofs of its proven blocks.
+pub trait Storage: Send {
+    /// Returns the number of blocks stored.
+    fn height(&self) -> Result<u64, StorageError>;
+
+    /// Returns the block with the given number, if it is stored.
+    fn block(&self, number: u64) -> Result<Option<Block>, StorageError>;
+
+    /// Returns the signed header of the block with the given number, if it is stored.
+    fn header(&self, number: u64) -> Result<Option<SignedBlockHeader>, StorageError>;
+
+    /// Returns the state roots after applying the last stored block.
+    fn roots(&self) -> Result<StateRoots, StorageError>;
+
+    /// Stores the block following the last stored block, along with the state roots after
+    /// applying it. Either both are written or neither is.
+    fn commit(&mut self, block: &Block, roots: &StateRoots) -> Result<(), StorageError>;
+
+    /// Returns the stored validity proofs of the blocks proven so far, in block order.
+    fn proofs(&self) -> Result<Vec<Proof>, StorageError>;
+
+    /// Stores the validity proof of the block following the last proven block.
+    fn commit_proof(&mut self, proof: &Proof) -> Result<(), StorageError>;
+}
+
+/// Storage keeping the chain in memory, lost when the node stops.
+#[derive(Default)]
+pub struct MemoryStorage {
+    /// The stored blocks, in order.
+    blocks: Vec<Block>,
+    /// The state roots after the last stored block, if any.
+    roots: Option<StateRoots>,
+    /// The validity proofs of the proven blocks, in order.
+    proofs: Vec<Proof>,
+}
+
+impl Storage for MemoryStorage {
+    fn height(&self) -> Result<u64, StorageError> {
+        Ok(self.blocks.len() as u64)
+    }
+
+    fn block(&self, number: u64) -> Result<Option<Block>, StorageError> {
+        Ok(self.blocks.get(number as usize).cloned())
+    }
+
+    fn header(&self, number: u64) -> Result<Option<SignedBlockHeader>, StorageError> {
+        Ok(self
+            .blocks
+            .get(number as usize)
+            .map(|block| block.signed().clone()))
+    }
+
+    fn roots(&self) -> Result<StateRoots, StorageError> {
+        Ok(self
+            .roots
+            .clone()
+            .unwrap_or_else(|| State::default().roots()))
+    }
+
+    fn commit(&mut self, block: &Block, roots: &StateRoots) -> Result<(), StorageError> {
+        expect_next(self.height()?, block)?;
+        self.blocks.push(block.clone());
+        self.roots = Some(roots.clone());
+        Ok(())
+    }
+
+    fn proofs(&self) -> Result<Vec<Proof>, StorageError> {
+        Ok(self.proofs.clone())
+    }
+
+    fn commit_proof(&mut self, proof: &Proof) -> Result<(), StorageError> {
+        self.proofs.push(proof.clone());
+        Ok(())
+    }
+}
+
+/// Storage keeping the chain in an embedded [redb] database on disk.
+/// Each block is committed in a single durable transaction, so that a crash never leaves
+/// a partially written block behind.
+pub struct DiskStorage {
+    db: Database,
+}
+
+impl DiskStorage {
+    /// Opens the database in the given data directory, creating both if they do not exist.
+    pub fn open(datadir: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let datadir = datadir.as_ref();
+        fs::create_dir_all(datadir).map_err(|e| StorageError::Backend(e.to_string()))?;
+        let db = Database::create(datadir.join("chain.redb")).map_err(backend)?;
+
+        // Create the tables up front so that reads never find them missing.
+        let tx = db.begin_write().map_err(backend)?;
+        tx.open_table(BLOCKS).map_err(backend)?;
+        tx.open_table(HEADERS).map_err(backend)?;
+        tx.open_table(ROOTS).map_err(backend)?;
+        tx.open_table(PROOFS).map_err(backend)?;
+        tx.commit().map_err(backend)?;
+        Ok(Self { db })
+    }
+
+    /// Reads and decodes the value stored for the block with the given number in a table.
+    fn get<T: DeserializeOwned>(
+        &self,
+        table: TableDefinition<u64, &[u8]>,
+        number: u64,
+    ) -> Result<Option<T>, StorageError> {
+        let tx = self.db.begin_read().map_err(backend)?;
+        let table = tx.open_table(table).map_err(backend)?;
+        let value = table.get(number).map_err(backend)?;
+        value.map(|value| decode(value.value())).transpose()
+    }
+}
+
+impl Storage for DiskStorage {
+    fn height(&self) -> Result<u64, StorageError> {
+        let tx = self.db.begin_read().map_err(backend)?;
+        let table = tx.open_table(HEADERS).map_err(backend)?;
+        table.len().map_err(backend)
+    }
+
+    fn block(&self, number: u64) -> Result<Option<Block>, StorageError> {
+        self.get(BLOCKS, number)
+    }
+
+    fn header(&self, number: u64) -> Result<Option<SignedBlockHeader>, StorageError> {
+        self.get(HEADERS, number)
+    }
+
+    fn roots(&self) -> Result<StateRoots, StorageError> {
+        let tx = self.db.begin_read().map_err(backend)?;
+        let table = tx.open_table(ROOTS).map_err(backend)?;
+        let value = table.get(HEAD).map_err(backend)?;
+        Ok(value
+            .map(|value| decode(value.value()))
+            .transpose()?
+            .unwrap_or_else(|| State::default().roots()))
+    }
+
+    fn commit(&mut self, block: &Block, roots: &StateRoots) -> Result<(), StorageError> {
+        expect_next(self.height()?, block)?;
+        let tx = self.db.begin_write().map_err(backend)?;
+        {
+            let number = block.number();
+            let mut blocks = tx.open_table(BLOCKS).map_err(backend)?;
+            blocks
+                .insert(number, bincode::serialize(block).unwrap().as_slice())
+                .map_err(backend)?;
+            let mut headers = tx.open_table(HEADERS).map_err(backend)?;
+            headers
+                .insert(
+                    number,
+                    bincode::serialize(block.signed()).unwrap().as_slice(),
+                )
+                .map_err(backend)?;
+            let mut head = tx.open_table(ROOTS).map_err(backend)?;
+            head.insert(HEAD, bincode::serialize(roots).unwrap().as_slice())
+                .map_err(backend)?;
+        }
+        tx.commit().map_err(backend)
+    }
+
+    fn proofs(&self) -> Result<Vec<Proof>, StorageError> {
+        let tx = self.db.begin_read().map_err(backend)?;
+        let table = tx.open_table(PROOFS).map_err(backend)?;
+        let mut proofs = vec![];
+        for entry in table.iter().map_err(backend)? {
+            let (_, value) = entry.map_err(backend)?;
+            proofs.push(decode(value.value())?);
+        }
+        Ok(proofs)
+    }
+
+    fn commit_proof(&mut self, proof: &Proof) -> Result<(), StorageError> {
+        let tx = self.db.begin_write().map_err(backend)?;
+        {
+            let mut proofs = tx.open_table(PROOFS).map_err(backend)?;
+            let number = proofs.len().map_err(backend)?;
+            proofs
+                .insert(number, bincode::serialize(proof).unwrap().as_slice())
+                .map_err(backend)?;
+        }
+        tx.commit().map_err(backend)
+    }
+}
+
+/// Checks that the block follows the given number of stored blocks.
+fn expect_next(height: u64, block: &Block) -> Result<(), StorageError> {
+    if block.number() != height {
+        return Err(StorageError::UnexpectedNumber {
+            expected: height,
+            actual: block.number(),
+        });
+    }
+    Ok(())
+}
+
+/// Wraps an error of the database backend.
+fn backend(e: impl Into<redb::Error>) -> StorageError {
+    StorageError::Backend(e.into().to_string())
+}
+
+/// Decodes a stored value.
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, StorageError> {
+    bincode::deserialize(bytes).map_err(|e| StorageError::Corrupt(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlockHeader, SignedTransaction, Signer, Transaction};
+
+    /// Creates a block with the given number holding a transaction.
+    fn block(signer: &Signer, number: u64) -> Block {
+        let header = BlockHeader {
+            sequencer: signer.address,
+            number,
+            view: 0,
+            timestamp: number,
+            parent_digest: None,
+            withdrawals_root: "0".to_string(),
+            transactions_root: "0".to_string(),
+            handover: None,
+        };
+        let tx = Transaction::dynamic(signer.address, 1, number);
+        Block::new(
+            SignedBlockHeader::new(header, signer),
+            vec![SignedTransaction::new(tx, signer)],
+        )
+    }
+
+    #[test]
+    fn test_disk_storage() {
+        let signer = Signer::random();
+        let datadir = std::env::temp_dir().join(format!("storage-{}", signer.address));
+        let mut state = State::default();
+        let first = block(&signer, 0);
+        let empty = state.roots();
+        state.apply(first.transactions());
+        let roots = state.roots();
+        {
+            let mut storage = DiskStorage::open(&datadir).unwrap();
+            assert_eq!(storage.height(), Ok(0));
+            assert_eq!(storage.roots(), Ok(empty));
+            storage.commit(&first, &roots).unwrap();
+
+            // Blocks must be stored in order.
+            assert_eq!(
+                storage.commit(&block(&signer, 2), &roots),
+                Err(StorageError::UnexpectedNumber {
+                    expected: 1,
+                    actual: 2
+                })
+            );
+        }
+
+        // Committed blocks and state roots survive reopening the database.
+        let storage = DiskStorage::open(&datadir).unwrap();
+        assert_eq!(storage.height(), Ok(1));
+        assert_eq!(storage.block(0), Ok(Some(first.clone())));
+        assert_eq!(storage.header(0), Ok(Some(first.signed().clone())));
+        assert_eq!(storage.block(1), Ok(None));
+        assert_eq!(storage.roots(), Ok(roots));
+
+        // Proofs are stored in block order and survive reopening the database.
+        let mut storage = storage;
+        let proof = Proof { data: vec![1] };
+        assert_eq!(storage.proofs(), Ok(vec![]));
+        storage.commit_proof(&proof).unwrap();
+        drop(storage);
+        let storage = DiskStorage::open(&datadir).unwrap();
+        assert_eq!(storage.proofs(), Ok(vec![proof]));
+        std::fs::remove_dir_all(datadir).unwrap();
+    }
+}
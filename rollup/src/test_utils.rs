@@ -0,0 +1,50 @@
+use crate::{
+    Address, Block, BlockHeader, Blockchain, CommitCertificate, SignedBlockHeader,
+    SignedTransaction, SignedVote, Signer, Vote, VoteKind,
+};
+
+/// Creates the header of the next block of the chain, sealed by the given sequencer and
+/// committing to the state after the given transactions.
+pub fn next_header(
+    chain: &Blockchain,
+    sequencer: Address,
+    transactions: &[SignedTransaction],
+) -> BlockHeader {
+    let roots = chain.execute(transactions).roots();
+    BlockHeader {
+        sequencer,
+        number: chain.height(),
+        view: 0,
+        timestamp: chain.height(),
+        parent_digest: chain.head().map(|b| b.hash()),
+        withdrawals_root: roots.withdrawals_root,
+        transactions_root: roots.transactions_root,
+        handover: None,
+    }
+}
+
+/// Finalizes the block with precommits from the given committee members.
+pub fn certify(block: Block, committee: &[&Signer]) -> Block {
+    let precommit = Vote {
+        kind: VoteKind::Precommit,
+        number: block.number(),
+        block_hash: block.hash(),
+    };
+    let precommits = committee
+        .iter()
+        .map(|signer| SignedVote::new(precommit.clone(), signer))
+        .collect();
+    block.with_certificate(CommitCertificate::new(precommits))
+}
+
+/// Creates the next block of the chain including the given transactions, sealed by the given
+/// signer and finalized by its precommit alone.
+pub fn certified_block(
+    chain: &Blockchain,
+    signer: &Signer,
+    transactions: Vec<SignedTransaction>,
+) -> Block {
+    let header = next_header(chain, signer.address, &transactions);
+    let block = Block::new(SignedBlockHeader::new(header, signer), transactions);
+    certify(block, &[signer])
+}
@@ -1,5 +1,4 @@
 use alloy_primitives::{keccak256, B256};
-use secp256k1::{Message, Secp256k1};
 use serde::{Deserialize, Serialize};
 
 use crate::signer::{Signature, Signer};
@@ -18,6 +17,29 @@ pub struct TransactionHeader {
     amount: u64,
     /// The nonce of the transaction.
     nonce: u64,
+    /// The point after which the transaction can no longer be included, if any.
+    expiry: Option<Expiry>,
+}
+
+/// The last block or time at which a transaction can be included.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Expiry {
+    /// The number of the last block that can include the transaction.
+    Block(u64),
+    /// The latest block timestamp, in milliseconds since the Unix epoch, at which the
+    /// transaction can be included.
+    Time(u64),
+}
+
+impl Expiry {
+    /// Returns whether the expiry has passed for a block with the given number and timestamp.
+    pub fn has_passed(&self, number: u64, timestamp: u64) -> bool {
+        match *self {
+            Expiry::Block(last) => number > last,
+            Expiry::Time(last) => timestamp > last,
+        }
+    }
 }
 
 /// A dynamic transaction containing a transaction header and dynamic fee data.
@@ -75,12 +97,25 @@ impl Transaction {
                 amount,
                 recipient: Address::random(),
                 nonce,
+                expiry: None,
             },
             max_fee_per_gas: 0,
             max_priority_fee_per_gas: 0,
         })
     }
 
+    /// Sets the fees a dynamic transaction is willing to pay. Withdrawals pay no fees.
+    pub fn with_fees(self, max_fee_per_gas: u64, max_priority_fee_per_gas: u64) -> Self {
+        match self {
+            Transaction::Dynamic(tx) => Transaction::Dynamic(DynamicTxData {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                ..tx
+            }),
+            withdrawal => withdrawal,
+        }
+    }
+
     /// Creates a new withdrawal transaction.
     pub fn withdrawal(sender: Address, amount: u64, nonce: u64, dest_chain: u64) -> Self {
         Transaction::Withdrawal(WithdrawalTxData {
@@ -90,11 +125,26 @@ impl Transaction {
                 recipient: sender,
                 nonce,
                 amount,
+                expiry: None,
             },
             dest_chain,
         })
     }
 
+    /// Sets the last block or time at which the transaction can be included.
+    pub fn with_expiry(self, expiry: Expiry) -> Self {
+        match self {
+            Transaction::Dynamic(mut tx) => {
+                tx.header.expiry = Some(expiry);
+                Transaction::Dynamic(tx)
+            }
+            Transaction::Withdrawal(mut tx) => {
+                tx.header.expiry = Some(expiry);
+                Transaction::Withdrawal(tx)
+            }
+        }
+    }
+
     /// Computes the hash of the transaction.
     pub fn hash(&self) -> B256 {
         match self {
@@ -110,6 +160,76 @@ impl Transaction {
             Transaction::Withdrawal(tx) => tx.header.sender,
         }
     }
+
+    /// Returns the recipient of the transaction.
+    pub fn recipient(&self) -> Address {
+        match self {
+            Transaction::Dynamic(tx) => tx.header.recipient,
+            Transaction::Withdrawal(tx) => tx.header.recipient,
+        }
+    }
+
+    /// Returns the identifier of the chain the transaction is intended for.
+    pub fn chain_id(&self) -> u64 {
+        match self {
+            Transaction::Dynamic(tx) => tx.header.chain_id,
+            Transaction::Withdrawal(tx) => tx.header.chain_id,
+        }
+    }
+
+    /// Returns the amount of value transferred by the transaction.
+    pub fn amount(&self) -> u64 {
+        match self {
+            Transaction::Dynamic(tx) => tx.header.amount,
+            Transaction::Withdrawal(tx) => tx.header.amount,
+        }
+    }
+
+    /// Returns the nonce of the transaction.
+    pub fn nonce(&self) -> u64 {
+        match self {
+            Transaction::Dynamic(tx) => tx.header.nonce,
+            Transaction::Withdrawal(tx) => tx.header.nonce,
+        }
+    }
+
+    /// Returns the last block or time at which the transaction can be included, if any.
+    pub fn expiry(&self) -> Option<Expiry> {
+        match self {
+            Transaction::Dynamic(tx) => tx.header.expiry,
+            Transaction::Withdrawal(tx) => tx.header.expiry,
+        }
+    }
+
+    /// Returns whether the transaction has expired for a block with the given number
+    /// and timestamp.
+    pub fn is_expired(&self, number: u64, timestamp: u64) -> bool {
+        self.expiry()
+            .is_some_and(|expiry| expiry.has_passed(number, timestamp))
+    }
+
+    /// Returns the maximum fee per gas the sender is willing to pay. Withdrawals pay no fees.
+    pub fn max_fee_per_gas(&self) -> u64 {
+        match self {
+            Transaction::Dynamic(tx) => tx.max_fee_per_gas,
+            Transaction::Withdrawal(_) => 0,
+        }
+    }
+
+    /// Returns the maximum priority fee per gas the sender is willing to pay. Withdrawals pay
+    /// no fees.
+    pub fn max_priority_fee_per_gas(&self) -> u64 {
+        match self {
+            Transaction::Dynamic(tx) => tx.max_priority_fee_per_gas,
+            Transaction::Withdrawal(_) => 0,
+        }
+    }
+
+    /// Returns the fee per gas paid to the sequencer: the priority fee, capped by the
+    /// maximum fee. Withdrawals pay no fees.
+    pub fn effective_tip(&self) -> u64 {
+        self.max_priority_fee_per_gas().min(self.max_fee_per_gas())
+    }
 }
 
 /// A signed transaction containing a transaction and signature.
@@ -129,16 +249,16 @@ impl SignedTransaction {
         }
     }
 
+    /// Recovers the address that signed the transaction.
+    /// Returns None if the signature is malformed.
+    pub fn signer(&self) -> Option<Address> {
+        self.signature.recover(self.transaction.hash())
+    }
+
     /// Verifies the signature of the [SignedTransaction] is valid and that it matches
     /// the address of the sender specified in the [TransactionHeader].
     pub fn verify(&self) -> bool {
-        let secp = Secp256k1::new();
-        let msg = Message::from_digest(self.transaction.hash().into());
-        let pk = secp.recover_ecdsa(&msg, &(&self.signature).into()).unwrap();
-        let address = Address::from(pk);
-        secp.verify_ecdsa(&msg, &(&self.signature).into(), &pk)
-            .is_ok()
-            && self.transaction.sender() == address
+        self.signer() == Some(self.transaction.sender())
     }
 }
 
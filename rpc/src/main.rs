@@ -1,67 +1,320 @@
 #[macro_use]
 extern crate rocket;
 
+use std::path::PathBuf;
 use std::sync::Arc;
 
-use rocket::State;
-use rocket::{serde::json::Json, Config};
-use rollup::{Blockchain, SignedTransaction, TransactionSubmitter};
+use clap::Parser;
+use log::{debug, info, warn};
+use rocket::response::status::Custom;
+use rocket::{serde::json::Json, Config, State};
+use rollup::http;
+use rollup::{
+    Address, Block, Blockchain, DiskStorage, EvidenceStore, Ingress, Mempool, SignedSubBlock,
+    SignedTransaction, TransactionSubmitter, PRICE_BUMP,
+};
+use serde::Deserialize;
 use serde_json::{json, Value};
+use tokio::sync::broadcast;
 use tokio::sync::Mutex;
 
-/// Accepts a transaction and adds it to the respective transaction pools.
+/// Configuration of the RPC process.
+#[derive(Parser)]
+struct Args {
+    /// The comma-separated addresses of the sequencers taking turns to seal blocks.
+    #[arg(long, env = "SEQUENCERS", value_delimiter = ',', required = true)]
+    sequencers: Vec<Address>,
+    /// The file to persist evidence of equivocating sequencers to.
+    /// Evidence is only kept in memory if unset.
+    #[arg(long, env = "EVIDENCE")]
+    evidence: Option<PathBuf>,
+    /// The directory to persist the chain to. The chain is only kept in memory if unset.
+    #[arg(long, env = "DATADIR")]
+    datadir: Option<PathBuf>,
+    /// The percentage by which a transaction must raise the fees of the pending transaction
+    /// with the same sender and nonce to replace it.
+    #[arg(long, env = "PRICE_BUMP", default_value_t = PRICE_BUMP)]
+    price_bump: u64,
+    /// The port to serve HTTP requests on.
+    #[arg(long, env = "PORT", default_value_t = 8001)]
+    port: u16,
+    /// The URL of a node to fetch blocks from on startup and whenever a gossiped block is
+    /// ahead of the chain, so that late or missed blocks do not stall the node.
+    #[arg(long, env = "SYNC_URL")]
+    sync_url: String,
+}
+
+/// A page of blocks served by the `/blocks` endpoint of a node.
+#[derive(Deserialize)]
+struct BlockPage {
+    blocks: Vec<Block>,
+}
+
+/// Accepts a transaction and adds it to the mempool. Invalid transactions are rejected with
+/// 400 Bad Request, transactions conflicting with known ones with 409 Conflict, and
+/// transactions turned away by a full mempool or a failing journal with 503 Service
+/// Unavailable.
 #[post("/", data = "<payload>")]
 async fn submit(
     submitter: &State<TransactionSubmitter>,
     payload: Json<SignedTransaction>,
-) -> Value {
+) -> Result<Value, Custom<Value>> {
     // Extract the transaction from the payload.
     let transaction = payload.into_inner();
     let tx_digest = transaction.transaction.hash();
 
-    // Add the transaction to the pool.
-    submitter.submit(transaction).await;
+    // Add the transaction to the mempool.
+    match submitter.submit(transaction).await {
+        // Respond with the transaction digest.
+        Ok(_) => Ok(json!({ "tx_digest": tx_digest.to_string() })),
+        Err(e) => Err(http::rejection(tx_digest.to_string(), e)),
+    }
+}
 
-    // Respond with the transaction digest.
-    json!({ "tx_digest": tx_digest.to_string() })
+/// Imports a finalized block if it follows the head of the chain, verifying it against the
+/// chain, and drops its transactions and those it expires from the mempool.
+async fn follow(chain: &Mutex<Blockchain>, mempool: &Mutex<Mempool>, block: Block) -> bool {
+    let mut chain = chain.lock().await;
+    if block.number() < chain.height() {
+        debug!("Ignoring block {} already imported", block.number());
+        return false;
+    }
+    if block.number() > chain.height() {
+        warn!(
+            "Ignoring block {} ahead of the chain at height {}",
+            block.number(),
+            chain.height()
+        );
+        return false;
+    }
+    let (number, hash, timestamp) = (block.number(), block.hash(), block.header().timestamp);
+    let transactions = block.transactions().to_vec();
+    match chain.import(block) {
+        Ok(()) => {
+            let mut mempool = mempool.lock().await;
+            mempool.remove_included(&transactions);
+            for expired in mempool.sweep(number, timestamp) {
+                info!("Dropped expired transaction: {}", expired);
+            }
+            info!("Imported block {} {}", number, hash);
+            true
+        }
+        Err(e) => {
+            warn!("Rejected block {}: {}", number, e);
+            false
+        }
+    }
 }
 
-/// Returns the head block of the blockchain.
-#[get("/")]
-async fn head(chain: &State<Arc<Mutex<Blockchain>>>) -> Value {
-    // Retrieve the head block from the sequencer and return it.
-    let head = chain.lock().await.head();
-    json!(head)
+/// Decodes a gossiped block and imports it, first catching up on any blocks missed before it
+/// from the node at `sync_url`. Returns whether the block was imported.
+async fn receive(
+    chain: &Mutex<Blockchain>,
+    mempool: &Mutex<Mempool>,
+    sync_url: &str,
+    data: &[u8],
+) -> bool {
+    let block = match serde_json::from_slice::<Block>(data) {
+        Ok(block) => block,
+        Err(e) => {
+            warn!("Failed to decode block: {}", e);
+            return false;
+        }
+    };
+    let height = chain.lock().await.height();
+    if block.number() > height {
+        if let Err(e) = sync(chain, mempool, sync_url, block.number()).await {
+            warn!("Failed to sync blocks from {}: {}", sync_url, e);
+        }
+    }
+    follow(chain, mempool, block).await
+}
+
+/// Fetches the head of the node at `url` and imports the blocks up to it.
+async fn catch_up(
+    chain: &Mutex<Blockchain>,
+    mempool: &Mutex<Mempool>,
+    url: &str,
+) -> Result<(), reqwest::Error> {
+    let head: Option<Block> = reqwest::get(format!("{url}/"))
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    match head {
+        Some(head) => sync(chain, mempool, url, head.number() + 1).await,
+        None => Ok(()),
+    }
+}
+
+/// Fetches the blocks between the head of the chain and the block numbered `to` from the
+/// node at `url` and imports them in order.
+async fn sync(
+    chain: &Mutex<Blockchain>,
+    mempool: &Mutex<Mempool>,
+    url: &str,
+    to: u64,
+) -> Result<(), reqwest::Error> {
+    let client = reqwest::Client::new();
+    loop {
+        let from = chain.lock().await.height();
+        if from >= to {
+            return Ok(());
+        }
+        let page: BlockPage = client
+            .get(format!("{url}/blocks"))
+            .query(&[("from", from), ("to", to - 1)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        // Stop on an empty page or a block that fails to import, rather than retrying.
+        if page.blocks.is_empty() {
+            warn!("Stopped syncing at block {}: not served by {}", from, url);
+            return Ok(());
+        }
+        for block in page.blocks {
+            let number = block.number();
+            if !follow(chain, mempool, block).await {
+                warn!("Stopped syncing at block {}: failed to import it", number);
+                return Ok(());
+            }
+        }
+    }
 }
 
 #[launch]
 #[tokio::main]
 async fn rocket() -> _ {
     env_logger::init();
+    let args = Args::parse();
+
     // Set up sequencer.
-    let pool = Arc::new(tokio::sync::Mutex::new(vec![]));
-    let chain = Arc::new(tokio::sync::Mutex::new(Blockchain::default()));
+    let mempool = Arc::new(tokio::sync::Mutex::new(
+        Mempool::default().with_price_bump(args.price_bump),
+    ));
+    let evidence = match args.evidence {
+        Some(path) => EvidenceStore::open(path).unwrap(),
+        None => EvidenceStore::default(),
+    };
+    let evidence = Arc::new(tokio::sync::Mutex::new(evidence));
+    let mut chain = Blockchain::new(args.sequencers);
+    if let Some(datadir) = args.datadir {
+        chain = chain
+            .with_storage(DiskStorage::open(datadir).unwrap())
+            .unwrap();
+    }
+    let chain = Arc::new(tokio::sync::Mutex::new(chain));
     let (tx_out, rx_out) = tokio::sync::mpsc::channel::<(Vec<u8>, String)>(32);
     let mut rx_in = p2p::Network::start(rx_out);
-    let submitter = TransactionSubmitter::new(pool, tx_out);
+    let submitter = TransactionSubmitter::new(Ingress::new(mempool.clone(), chain.clone()), tx_out);
+    let preconfirmations = broadcast::channel::<SignedSubBlock>(64).0;
 
-    // Spawn block producing sequencer task.
+    // Catch up on the chain, then follow it and the other gossip of the sequencers.
+    let store = evidence.clone();
+    let stream = preconfirmations.clone();
+    let follower = chain.clone();
+    let sync_url = args.sync_url;
     tokio::task::spawn(async move {
+        if let Err(e) = catch_up(&follower, &mempool, &sync_url).await {
+            warn!("Failed to sync blocks from {}: {}", sync_url, e);
+        }
         loop {
             let msg = rx_in.recv().await.unwrap();
-            println!("RPC Received message: {:?}", msg);
+            match msg.topic.as_str() {
+                // Import finalized blocks, catching up on any missed blocks first.
+                p2p::BLOCKS_TOPIC => {
+                    receive(&follower, &mempool, &sync_url, &msg.data).await;
+                }
+
+                // Collect evidence of equivocating sequencers.
+                p2p::EVIDENCE_TOPIC => {
+                    if let Ok(item) = serde_json::from_slice(&msg.data) {
+                        if let Err(e) = store.lock().await.add(item) {
+                            warn!("Failed to persist evidence: {}", e);
+                        }
+                    }
+                }
+
+                // Stream verified preconfirmations to subscribers.
+                p2p::PRECONFIRMATIONS_TOPIC => {
+                    if let Ok(preconfirmation) = serde_json::from_slice::<SignedSubBlock>(&msg.data)
+                    {
+                        if preconfirmation.verify() {
+                            // There may be no subscribers.
+                            let _ = stream.send(preconfirmation);
+                        }
+                    }
+                }
+                _ => {}
+            }
         }
     });
 
     // Launch the HTTP server.
-    let mut config = Config {
+    let config = Config {
         log_level: rocket::config::LogLevel::Critical,
+        port: args.port,
         ..Config::debug_default()
     };
-    config.port = 8001;
     rocket::build()
         .configure(config)
-        .mount("/", routes![submit, head])
+        .mount("/", routes![submit])
+        .mount("/", http::routes())
         .manage(submitter)
         .manage(chain)
+        .manage(evidence)
+        .manage(preconfirmations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rollup::test_utils::{certified_block, certify, next_header};
+    use rollup::{BlockHeader, SignedBlockHeader, Signer};
+
+    #[tokio::test]
+    async fn test_receive() {
+        let sequencer = Signer::random();
+        let chain = Mutex::new(Blockchain::new(vec![sequencer.address]));
+        let mempool = Mutex::new(Mempool::default());
+        let gossip = |block: &Block| serde_json::to_vec(block).unwrap();
+        // No node serves blocks to sync from.
+        let receive = |data: Vec<u8>| {
+            let (chain, mempool) = (&chain, &mempool);
+            async move { receive(chain, mempool, "http://127.0.0.1:9", &data).await }
+        };
+
+        // Undecodable gossip is dropped.
+        assert!(!receive(b"not a block".to_vec()).await);
+
+        // A block signed by a key other than its sequencer's is rejected.
+        let header = next_header(&*chain.lock().await, sequencer.address, &[]);
+        let forged = Block::new(
+            SignedBlockHeader::new(header.clone(), &Signer::random()),
+            vec![],
+        );
+        let forged = certify(forged, &[&sequencer]);
+        assert!(!receive(gossip(&forged)).await);
+
+        // A block ahead of the chain is not imported when the blocks before it cannot be
+        // fetched.
+        let header = BlockHeader {
+            number: 1,
+            ..header
+        };
+        let ahead = Block::new(SignedBlockHeader::new(header, &sequencer), vec![]);
+        let ahead = certify(ahead, &[&sequencer]);
+        assert!(!receive(gossip(&ahead)).await);
+
+        // A valid block following the head is imported.
+        let valid = certified_block(&*chain.lock().await, &sequencer, vec![]);
+        assert!(receive(gossip(&valid)).await);
+        assert_eq!(chain.lock().await.head(), Some(valid.clone()));
+
+        // Blocks already imported are ignored.
+        assert!(!receive(gossip(&valid)).await);
+        assert_eq!(chain.lock().await.height(), 1);
+    }
 }
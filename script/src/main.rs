@@ -1,4 +1,4 @@
-use rollup::{Block, SignedTransaction, Signer, Transaction, BLOCK_PERIOD};
+use rollup::{Address, Block, SignedTransaction, Signer, Transaction, BLOCK_PERIOD};
 use secp256k1::SecretKey;
 use tokio::process::Command;
 
@@ -6,14 +6,29 @@ use tokio::process::Command;
 const SEQUENCER_URL: &str = "127.0.0.1:8000";
 const RPC_URL: &str = "127.0.0.1:8001";
 
+/// Port of the first sequencer. Further sequencers listen on every other port after it
+/// to leave room for the RPC.
+const SEQUENCER_PORT: u16 = 8000;
+
+/// Joins sequencer addresses into a comma-separated list.
+fn join(sequencers: &[Address]) -> String {
+    sequencers
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 /// Runs the sequencer process and blocks on it's completion.
-async fn run_sequencer(sk: SecretKey) {
+async fn run_sequencer(sk: SecretKey, sequencers: Vec<Address>, port: u16) {
     let mut sequencer = Command::new("cargo")
         .arg("run")
         .arg("--bin")
         .arg("sequencer")
         .arg("--")
         .env("KEY", hex::encode(sk.secret_bytes()))
+        .env("SEQUENCERS", join(&sequencers))
+        .env("PORT", port.to_string())
         .kill_on_drop(true)
         .spawn()
         .expect("Failed to start sequencer process");
@@ -23,12 +38,14 @@ async fn run_sequencer(sk: SecretKey) {
         .expect("Failure while waiting for sequencer process");
 }
 
-async fn run_rpc() {
+async fn run_rpc(sequencers: Vec<Address>) {
     let mut sequencer = Command::new("cargo")
         .arg("run")
         .arg("--bin")
         .arg("rpc")
         .arg("--")
+        .env("SEQUENCERS", join(&sequencers))
+        .env("SYNC_URL", format!("http://{}", SEQUENCER_URL))
         .kill_on_drop(true)
         .spawn()
         .expect("Failed to start sequencer process");
@@ -45,6 +62,7 @@ async fn send_transaction(tx: SignedTransaction) -> Result<reqwest::Response, re
         .json(&tx)
         .send()
         .await
+        .and_then(|res| res.error_for_status())
 }
 
 /// Sleeps for a short period of time and prints an error message.
@@ -61,10 +79,10 @@ async fn handle_request_err(e: reqwest::Error) {
 async fn tx_loop() {
     // Wait for the sequencer to start.
     tokio::time::sleep(BLOCK_PERIOD).await;
-    for i in 0.. {
+    for i in 1.. {
         // Send a deposit transaction.
         let signer = Signer::random();
-        let transaction = Transaction::dynamic(signer.address, i, i);
+        let transaction = Transaction::dynamic(signer.address, i, 0);
         let signed = SignedTransaction::new(transaction, &signer);
         if let Err(e) = send_transaction(signed).await {
             handle_request_err(e).await;
@@ -73,7 +91,7 @@ async fn tx_loop() {
 
         // Send a withdrawal transaction.
         let dest_chain = 1u64;
-        let transaction = Transaction::withdrawal(signer.address, i, dest_chain, i);
+        let transaction = Transaction::withdrawal(signer.address, i, 1, dest_chain);
         let signed = SignedTransaction::new(transaction, &signer);
         if let Err(e) = send_transaction(signed).await {
             handle_request_err(e).await;
@@ -114,15 +132,23 @@ async fn head_loop() {
 
 #[tokio::main]
 async fn main() {
-    // Random permissioned sequencer.
-    let Signer { sk, .. } = Signer::random();
+    // Random permissioned sequencers taking turns to seal blocks.
+    let count: u16 = std::env::var("SEQUENCER_COUNT")
+        .map(|count| count.parse().expect("Invalid sequencer count"))
+        .unwrap_or(1);
+    let signers: Vec<Signer> = (0..count).map(|_| Signer::random()).collect();
+    let sequencers: Vec<Address> = signers.iter().map(|s| s.address).collect();
 
-    // Run the sequencer.
-    tokio::spawn(async move {
-        run_sequencer(sk).await;
-    });
+    // Run the sequencers.
+    for (i, Signer { sk, .. }) in signers.into_iter().enumerate() {
+        let sequencers = sequencers.clone();
+        let port = SEQUENCER_PORT + 2 * i as u16;
+        tokio::spawn(async move {
+            run_sequencer(sk, sequencers, port).await;
+        });
+    }
     tokio::spawn(async move {
-        run_rpc().await;
+        run_rpc(sequencers).await;
     });
 
     // Continuously check the head block.
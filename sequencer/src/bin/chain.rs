@@ -0,0 +1,70 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use rollup::{Address, Blockchain, DiskStorage};
+
+/// Shares chains between nodes by exporting ranges of blocks to portable archive files and
+/// importing them into the chain of another node.
+#[derive(Parser)]
+struct Args {
+    /// The data directory holding the chain.
+    #[arg(long, env = "DATADIR")]
+    datadir: PathBuf,
+    /// The comma-separated addresses of the sequencers taking turns to seal blocks.
+    #[arg(long, env = "SEQUENCERS", value_delimiter = ',', required = true)]
+    sequencers: Vec<Address>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// The operation to perform on the chain.
+#[derive(Subcommand)]
+enum Command {
+    /// Exports a range of blocks to an archive file.
+    Export {
+        /// The number of the first block to export.
+        #[arg(long, default_value_t = 0)]
+        from: u64,
+        /// The number of the last block to export. Defaults to the head of the chain.
+        #[arg(long)]
+        to: Option<u64>,
+        /// Compresses the blocks with zstd.
+        #[arg(long)]
+        compress: bool,
+        /// The archive file to write.
+        file: PathBuf,
+    },
+    /// Imports the blocks of an archive file after the head of the chain, validating
+    /// every block.
+    Import {
+        /// The archive file to read.
+        file: PathBuf,
+    },
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let storage = DiskStorage::open(&args.datadir)?;
+    let mut chain = Blockchain::new(args.sequencers).with_storage(storage)?;
+    match args.command {
+        Command::Export {
+            from,
+            to,
+            compress,
+            file,
+        } => {
+            let head = chain.head().ok_or("the chain is empty")?;
+            let to = to.unwrap_or(head.number());
+            let writer = BufWriter::new(File::create(&file)?);
+            let count = rollup::export(&chain, from, to, writer, compress)?;
+            println!("Exported {count} blocks to {}", file.display());
+        }
+        Command::Import { file } => {
+            let count = rollup::import(&mut chain, File::open(&file)?)?;
+            println!("Imported {count} blocks from {}", file.display());
+        }
+    }
+    Ok(())
+}
@@ -1,65 +1,556 @@
 #[macro_use]
 extern crate rocket;
 
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
-use rocket::State;
+use alloy_primitives::B256;
+use clap::{Parser, ValueEnum};
+use l1::L1;
+use log::info;
+use rocket::http::Status;
+use rocket::response::status::Custom;
 use rocket::{serde::json::Json, Config};
-use rollup::{Blockchain, Sequencer, SignedTransaction, TransactionSubmitter};
+use rocket::{Build, Rocket, State};
+use rollup::http;
+use rollup::{
+    Address, BatchSubmitter, BlockSealer, Blockchain, DiskStorage, EvidenceStore, FileSink,
+    Handover, KeyRotator, Mempool, MerkleProof, MockProver, SealingMode, Sequencer, SignedHandover,
+    SignedTransaction, Signer, TransactionSubmitter, PRICE_BUMP,
+};
+use serde::Deserialize;
 use serde_json::{json, Value};
 use tokio::sync::Mutex;
 
-/// Accepts a transaction and adds it to the respective transaction pools.
+/// When the sequencer seals blocks in its slot.
+#[derive(Clone, Copy, ValueEnum)]
+enum Sealing {
+    /// Seal a block every block period.
+    Interval,
+    /// Seal a block as soon as a transaction arrives.
+    Instant,
+    /// Seal a block only when requested through the `/admin/seal` endpoint.
+    Manual,
+    /// Seal a block every block period unless it would be empty and the chain has been
+    /// idle for less than the maximum idle time.
+    SkipEmpty,
+}
+
+/// Configuration of the sequencer process.
+#[derive(Parser)]
+struct Args {
+    /// The secret key used to sign blocks.
+    #[arg(long, env = "KEY")]
+    key: Signer,
+    /// The secret key to hand sequencing over to once a handover signed by the key above is
+    /// posted to the `/handover` endpoint. Handovers are rejected if unset.
+    #[arg(long, env = "NEXT_KEY")]
+    next_key: Option<Signer>,
+    /// The comma-separated addresses of the sequencers taking turns to seal blocks.
+    /// Defaults to this sequencer alone.
+    #[arg(long, env = "SEQUENCERS", value_delimiter = ',')]
+    sequencers: Vec<Address>,
+    /// The file to persist evidence of equivocating sequencers to.
+    /// Evidence is only kept in memory if unset.
+    #[arg(long, env = "EVIDENCE")]
+    evidence: Option<PathBuf>,
+    /// The file to journal admitted transactions to so that they survive a restart.
+    /// Pending transactions are only kept in memory if unset.
+    #[arg(long, env = "JOURNAL")]
+    journal: Option<PathBuf>,
+    /// The directory to post batches of finalized blocks to, standing in for a
+    /// data availability layer. Batches are not posted if unset.
+    #[arg(long, env = "BATCHES")]
+    batches: Option<PathBuf>,
+    /// The number of blocks in each posted batch.
+    #[arg(long, env = "BATCH_SIZE", default_value_t = 10)]
+    batch_size: usize,
+    /// The number of milliseconds between blocks.
+    #[arg(long, env = "BLOCK_PERIOD", default_value_t = 2000,
+          value_parser = clap::value_parser!(u64).range(1..))]
+    block_period: u64,
+    /// When to seal blocks.
+    #[arg(long, env = "SEALING", value_enum, default_value_t = Sealing::Interval)]
+    sealing: Sealing,
+    /// The maximum number of milliseconds between blocks when skipping empty blocks.
+    #[arg(long, env = "MAX_IDLE", default_value_t = 60000)]
+    max_idle: u64,
+    /// The directory to persist the chain to. The chain is only kept in memory if unset.
+    #[arg(long, env = "DATADIR")]
+    datadir: Option<PathBuf>,
+    /// The percentage by which a transaction must raise the fees of the pending transaction
+    /// with the same sender and nonce to replace it.
+    #[arg(long, env = "PRICE_BUMP", default_value_t = PRICE_BUMP)]
+    price_bump: u64,
+    /// The port to serve HTTP requests on.
+    #[arg(long, env = "PORT", default_value_t = 8000)]
+    port: u16,
+}
+
+/// Accepts a transaction and adds it to the mempool. Invalid transactions are rejected with
+/// 400 Bad Request, transactions conflicting with known ones with 409 Conflict, and
+/// transactions turned away by a full mempool or a failing journal with 503 Service
+/// Unavailable.
 #[post("/", data = "<payload>")]
 async fn submit(
     submitter: &State<TransactionSubmitter>,
     payload: Json<SignedTransaction>,
-) -> Value {
+) -> Result<Value, Custom<Value>> {
     // Extract the transaction from the payload.
     let transaction = payload.into_inner();
     let tx_digest = transaction.transaction.hash();
 
-    // Add the transaction to the pool.
-    submitter.submit(transaction).await;
+    // Add the transaction to the mempool.
+    match submitter.submit(transaction).await {
+        // Respond with the transaction digest and the sequencer's promise to include it, if any.
+        Ok(receipt) => Ok(json!({ "tx_digest": tx_digest.to_string(), "receipt": receipt })),
+        Err(e) => Err(http::rejection(tx_digest.to_string(), e)),
+    }
+}
+
+/// Requests a handover of sequencing to the key configured with `--next-key`. The handover
+/// must be signed by this sequencer's key and name the configured key, and is rejected with
+/// 400 Bad Request otherwise.
+#[post("/handover", data = "<payload>")]
+async fn handover(
+    rotator: &State<KeyRotator>,
+    payload: Json<SignedHandover>,
+) -> Result<Value, Custom<Value>> {
+    let handover = payload.into_inner();
+
+    // Record the handover in the next sealed block.
+    if let Err(e) = rotator.rotate(&handover).await {
+        return Err(Custom(
+            Status::BadRequest,
+            json!({ "error": e.to_string() }),
+        ));
+    }
+
+    // Respond with the address of the next sequencer.
+    let Handover {
+        next, effective_at, ..
+    } = handover.handover();
+    Ok(json!({ "sequencer": next, "effective_at": effective_at }))
+}
+
+/// Requests that the next block is sealed, when sealing manually.
+#[post("/admin/seal")]
+async fn seal(sealer: &State<BlockSealer>) -> Value {
+    sealer.seal();
+    json!({ "requested": true })
+}
+
+/// Returns the status of the block with the given number, if it exists.
+#[get("/blocks/<number>/status")]
+async fn status(chain: &State<Arc<Mutex<Blockchain>>>, number: u64) -> Option<Value> {
+    let status = chain.lock().await.status(number)?;
+    Some(json!({ "number": number, "status": status }))
+}
+
+/// Returns the status of the transaction with the given hash in the mempool, including
+/// whether it was dropped after expiring.
+#[get("/transactions/<hash>/status")]
+async fn transaction_status(mempool: &State<Arc<Mutex<Mempool>>>, hash: &str) -> Option<Value> {
+    let hash: B256 = hash.parse().ok()?;
+    let status = mempool.lock().await.status(&hash)?;
+    Some(json!({ "hash": hash, "status": status }))
+}
+
+/// A request to finalize the exit of a withdrawal on the L1.
+#[derive(Deserialize)]
+struct ExitRequest {
+    /// The index of the batch whose withdrawals root the proof is against.
+    batch: u64,
+    /// The proof of inclusion of the withdrawal.
+    proof: MerkleProof,
+}
 
-    // Respond with the transaction digest.
-    json!({ "tx_digest": tx_digest.to_string() })
+/// Finalizes the exit of a proven withdrawal on the mock L1.
+#[post("/l1/exits", data = "<payload>")]
+async fn exit(l1: &State<Arc<Mutex<L1>>>, payload: Json<ExitRequest>) -> Value {
+    let ExitRequest { batch, proof } = payload.into_inner();
+    match l1.lock().await.finalize_withdrawal(batch, &proof) {
+        Ok(()) => json!({ "withdrawal": proof.leaf, "finalized": true }),
+        Err(e) => json!({ "withdrawal": proof.leaf, "finalized": false, "error": e.to_string() }),
+    }
 }
 
-/// Returns the head block of the blockchain.
-#[get("/")]
-async fn head(chain: &State<Arc<Mutex<Blockchain>>>) -> Value {
-    // Retrieve the head block from the sequencer and return it.
-    let head = chain.lock().await.head();
-    json!(head)
+/// A request to deposit funds from the L1 to the L2.
+#[derive(Deserialize)]
+struct DepositRequest {
+    /// The address of the recipient on the L2.
+    recipient: Address,
+    /// The amount of value to deposit.
+    amount: u64,
+}
+
+/// Deposits funds on the mock L1, emitting a deposit event.
+#[post("/l1/deposits", data = "<payload>")]
+async fn deposit(l1: &State<Arc<Mutex<L1>>>, payload: Json<DepositRequest>) -> Value {
+    let DepositRequest { recipient, amount } = payload.into_inner();
+    json!(l1.lock().await.deposit(recipient, amount))
+}
+
+/// Returns the batch committed to the mock L1 at the given index.
+#[get("/l1/batches/<index>")]
+async fn batch(l1: &State<Arc<Mutex<L1>>>, index: u64) -> Option<Value> {
+    l1.lock().await.batch(index).map(|batch| json!(batch))
 }
 
 #[launch]
 #[tokio::main]
 async fn rocket() -> _ {
     env_logger::init();
+    let args = Args::parse();
+
     // Set up sequencer.
-    let sk = std::env::var("KEY").unwrap();
-    let pool = Arc::new(tokio::sync::Mutex::new(vec![]));
-    let chain = Arc::new(tokio::sync::Mutex::new(Blockchain::default()));
+    let signer = args.key;
+    let sequencers = if args.sequencers.is_empty() {
+        vec![signer.address]
+    } else {
+        args.sequencers
+    };
+    let mempool = Arc::new(tokio::sync::Mutex::new(
+        Mempool::default().with_price_bump(args.price_bump),
+    ));
+    let evidence = match args.evidence {
+        Some(path) => EvidenceStore::open(path).unwrap(),
+        None => EvidenceStore::default(),
+    };
+    let evidence = Arc::new(tokio::sync::Mutex::new(evidence));
+    let mut chain = Blockchain::new(sequencers);
+    if let Some(datadir) = args.datadir {
+        chain = chain
+            .with_storage(DiskStorage::open(datadir).unwrap())
+            .unwrap();
+    }
+    let chain = Arc::new(tokio::sync::Mutex::new(chain));
     let (tx_out, rx_out) = tokio::sync::mpsc::channel::<(Vec<u8>, String)>(32);
     let rx_in = p2p::Network::start(rx_out);
-    let mut sequencer = Sequencer::new(sk.as_str(), pool.clone(), chain.clone(), rx_in);
-    let submitter = TransactionSubmitter::new(pool, tx_out);
+    let block_period = Duration::from_millis(args.block_period);
+    let mode = match args.sealing {
+        Sealing::Interval => SealingMode::Interval,
+        Sealing::Instant => SealingMode::Instant,
+        Sealing::Manual => SealingMode::Manual,
+        Sealing::SkipEmpty => SealingMode::SkipEmpty {
+            max_idle: Duration::from_millis(args.max_idle),
+        },
+    };
+    let mut sequencer = Sequencer::new(
+        signer,
+        mempool.clone(),
+        chain.clone(),
+        rx_in,
+        tx_out,
+        evidence.clone(),
+    )
+    .with_block_period(block_period)
+    .with_mode(mode);
+    if let Some(next_key) = args.next_key {
+        sequencer = sequencer.with_next_key(next_key);
+    }
+    if let Some(path) = args.journal {
+        let restored = sequencer.open_journal(path).await.unwrap();
+        info!("Restored {} journaled transactions", restored);
+    }
+    let l1 = Arc::new(tokio::sync::Mutex::new(L1::default()));
+    let server = server(&sequencer, chain.clone(), mempool, evidence, l1.clone());
+
+    // Spawn task committing the roots of finalized blocks to the mock L1.
+    tokio::task::spawn(l1::settle(l1.clone(), chain.clone(), sequencer.subscribe()));
+
+    // Spawn task posting batches of finalized blocks.
+    if let Some(dir) = args.batches {
+        let sink = FileSink::open(dir).unwrap();
+        let submitter = BatchSubmitter::new(sink, args.batch_size).unwrap();
+        tokio::task::spawn(submitter.run(chain.clone(), sequencer.subscribe()));
+    }
 
     // Spawn block producing sequencer task.
     tokio::task::spawn(async move {
         sequencer.run().await;
     });
 
+    // Spawn block proving task.
+    tokio::task::spawn(rollup::prove(
+        chain.clone(),
+        MockProver,
+        MockProver,
+        block_period,
+    ));
+
     // Launch the HTTP server.
     let config = Config {
         log_level: rocket::config::LogLevel::Critical,
+        port: args.port,
         ..Config::debug_default()
     };
+    server.configure(config)
+}
+
+/// Builds the HTTP server exposing the sequencer and the state it shares.
+fn server(
+    sequencer: &Sequencer,
+    chain: Arc<Mutex<Blockchain>>,
+    mempool: Arc<Mutex<Mempool>>,
+    evidence: Arc<Mutex<EvidenceStore>>,
+    l1: Arc<Mutex<L1>>,
+) -> Rocket<Build> {
     rocket::build()
-        .configure(config)
-        .mount("/", routes![submit, head])
-        .manage(submitter)
+        .mount(
+            "/",
+            routes![
+                submit,
+                status,
+                transaction_status,
+                handover,
+                seal,
+                exit,
+                deposit,
+                batch
+            ],
+        )
+        .mount("/", http::routes())
+        .manage(sequencer.submitter())
+        .manage(sequencer.rotator())
+        .manage(sequencer.sealer())
+        .manage(sequencer.preconfirmations())
         .manage(chain)
+        .manage(mempool)
+        .manage(evidence)
+        .manage(l1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::local::asynchronous::Client;
+    use rollup::{Block, State as L2State, Transaction};
+
+    /// Runs a sole sequencer sealing in the given mode, without peers, and returns a client
+    /// of its HTTP server.
+    async fn client(mode: SealingMode) -> Client {
+        let signer = Signer::random();
+        let chain = Arc::new(Mutex::new(Blockchain::new(vec![signer.address])));
+        let mempool = Arc::new(Mutex::new(Mempool::default()));
+        let evidence = Arc::new(Mutex::new(EvidenceStore::default()));
+        let (tx_in, rx_in) = tokio::sync::mpsc::channel(32);
+        let (tx_out, mut rx_out) = tokio::sync::mpsc::channel(32);
+        tokio::spawn(async move {
+            // Keep the inbound channel open for as long as the gossip is drained.
+            let _tx_in = tx_in;
+            while rx_out.recv().await.is_some() {}
+        });
+        let mut sequencer = Sequencer::new(
+            signer,
+            mempool.clone(),
+            chain.clone(),
+            rx_in,
+            tx_out,
+            evidence.clone(),
+        )
+        .with_mode(mode);
+        let l1 = Arc::new(Mutex::new(L1::default()));
+        let server = server(&sequencer, chain, mempool, evidence, l1);
+        tokio::spawn(async move { sequencer.run().await });
+        Client::tracked(server).await.unwrap()
+    }
+
+    #[rocket::async_test]
+    async fn test_submitted_withdrawal_changes_withdrawals_root() {
+        let client = client(SealingMode::Instant).await;
+
+        // Submit a withdrawal over HTTP.
+        let sender = Signer::random();
+        let withdrawal = Transaction::withdrawal(sender.address, 1, 0, 1);
+        let withdrawal = SignedTransaction::new(withdrawal, &sender);
+        let response = client.post("/").json(&withdrawal).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = response.into_json().await.unwrap();
+        assert!(body["receipt"].is_object());
+
+        // The sealed block includes the withdrawal and commits to it in the withdrawals root.
+        let head = loop {
+            let response = client.get("/").dispatch().await;
+            if let Some(head) = response.into_json::<Option<Block>>().await.unwrap() {
+                break head;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        let mut state = L2State::default();
+        let empty = state.roots();
+        state.apply(std::slice::from_ref(&withdrawal));
+        assert_eq!(head.transactions(), [withdrawal]);
+        assert_ne!(head.header().withdrawals_root, empty.withdrawals_root);
+        assert_eq!(head.header().state_roots(), state.roots());
+    }
+
+    #[rocket::async_test]
+    async fn test_transaction_indexes() {
+        let client = client(SealingMode::Instant).await;
+        let sender = Signer::random();
+        let mut hashes = vec![];
+        for nonce in 0..3 {
+            let transaction = Transaction::dynamic(sender.address, 1, nonce);
+            let transaction = SignedTransaction::new(transaction, &sender);
+            hashes.push(transaction.transaction.hash());
+            let response = client.post("/").json(&transaction).dispatch().await;
+            assert_eq!(response.status(), Status::Ok);
+        }
+
+        // Once included, transactions are located by hash.
+        let uri = format!("/transactions/{}/location", hashes[2]);
+        let location = loop {
+            let response = client.get(&uri).dispatch().await;
+            if response.status() == Status::Ok {
+                break response.into_json::<Value>().await.unwrap();
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(location["hash"], json!(hashes[2]));
+        let response = client.get(format!("/transactions/{}/location", B256::random()));
+        assert_eq!(response.dispatch().await.status(), Status::NotFound);
+
+        // The sender's history is listed in pages.
+        let uri = format!(
+            "/addresses/{}/transactions?offset=1&limit=1",
+            sender.address
+        );
+        let page: Value = client.get(uri).dispatch().await.into_json().await.unwrap();
+        assert_eq!(page["total"], 3);
+        assert_eq!(page["transactions"].as_array().unwrap().len(), 1);
+        assert_eq!(page["transactions"][0]["hash"], json!(hashes[1]));
+    }
+
+    #[rocket::async_test]
+    async fn test_block_lookups() {
+        let client = client(SealingMode::Instant).await;
+        let sender = Signer::random();
+        let transaction =
+            SignedTransaction::new(Transaction::dynamic(sender.address, 1, 0), &sender);
+        let hash = transaction.transaction.hash();
+        client.post("/").json(&transaction).dispatch().await;
+
+        // Included transactions are served along with their location.
+        let uri = format!("/transactions/{hash}");
+        let included: Value = loop {
+            let response = client.get(&uri).dispatch().await;
+            if response.status() == Status::Ok {
+                break response.into_json().await.unwrap();
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(included["transaction"], json!(transaction));
+        assert_eq!(included["number"], 0);
+        assert_eq!(included["index"], 0);
+
+        // Blocks and headers are served by number and hash.
+        let get = |uri: String| {
+            let client = &client;
+            async move {
+                let response = client.get(uri).dispatch().await;
+                (response.status(), response.into_json::<Value>().await)
+            }
+        };
+        let (status, block) = get("/blocks/0".to_string()).await;
+        assert_eq!(status, Status::Ok);
+        let block: Block = serde_json::from_value(block.unwrap()).unwrap();
+        assert_eq!(block.transactions(), [transaction]);
+        let (_, by_hash) = get(format!("/blocks/hash/{}", block.hash())).await;
+        assert_eq!(by_hash, Some(json!(block)));
+        let (_, header) = get("/headers/0".to_string()).await;
+        assert_eq!(header, Some(json!(block.signed())));
+
+        // Ranges of blocks are served in pages.
+        let (_, page) = get("/blocks?from=0&to=5".to_string()).await;
+        let page = page.unwrap();
+        assert_eq!(page["blocks"], json!([block]));
+        assert_eq!(page["next"], Value::Null);
+
+        // Missing blocks, headers and transactions are not found.
+        for uri in [
+            "/blocks/1".to_string(),
+            "/blocks/1/status".to_string(),
+            format!("/blocks/hash/{}", B256::random()),
+            "/headers/1".to_string(),
+            format!("/transactions/{}", B256::random()),
+        ] {
+            assert_eq!(get(uri).await.0, Status::NotFound);
+        }
+    }
+
+    #[rocket::async_test]
+    async fn test_rejected_submissions() {
+        let client = client(SealingMode::Manual).await;
+        let sender = Signer::random();
+        let submit = |transaction: Transaction, signer: &Signer| {
+            let transaction = SignedTransaction::new(transaction, signer);
+            let client = &client;
+            async move {
+                let response = client.post("/").json(&transaction).dispatch().await;
+                let status = response.status();
+                let body: Value = response.into_json().await.unwrap();
+                (status, body["error"]["code"].clone())
+            }
+        };
+
+        // Invalid transactions are rejected as bad requests.
+        let (status, code) = submit(Transaction::dynamic(sender.address, 0, 0), &sender).await;
+        assert_eq!((status, code), (Status::BadRequest, json!("zero_amount")));
+        let forged = Transaction::dynamic(sender.address, 1, 0);
+        let (status, code) = submit(forged, &Signer::random()).await;
+        assert_eq!((status, code), (Status::BadRequest, json!("wrong_sender")));
+
+        // Resubmitting a preconfirmed transaction is not a conflict, but replacing it is.
+        let transaction = Transaction::dynamic(sender.address, 1, 0);
+        let (status, _) = submit(transaction.clone(), &sender).await;
+        assert_eq!(status, Status::Ok);
+        let (status, _) = submit(transaction, &sender).await;
+        assert_eq!(status, Status::Ok);
+        let replacement = Transaction::dynamic(sender.address, 2, 0);
+        let (status, code) = submit(replacement, &sender).await;
+        assert_eq!((status, code), (Status::Conflict, json!("preconfirmed")));
+
+        // Transactions conflicting with pooled ones are rejected as conflicts.
+        let queued = Transaction::dynamic(sender.address, 1, 2);
+        let (status, _) = submit(queued.clone(), &sender).await;
+        assert_eq!(status, Status::Ok);
+        let (status, code) = submit(queued, &sender).await;
+        assert_eq!((status, code), (Status::Conflict, json!("already_known")));
+    }
+
+    #[rocket::async_test]
+    async fn test_rejected_handovers() {
+        let client = client(SealingMode::Manual).await;
+
+        // Handovers not signed by the sequencer's key are rejected as bad requests.
+        let stranger = Signer::random();
+        let handover = Handover {
+            current: stranger.address,
+            next: Signer::random().address,
+            effective_at: 1,
+        };
+        let handover = SignedHandover::new(handover, &stranger);
+        let response = client.post("/handover").json(&handover).dispatch().await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        // Malformed handovers are refused without reaching the sequencer.
+        let response = client
+            .post("/handover")
+            .json(&json!({ "key": "0x00", "effective_at": 1 }))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+    }
+
+    #[test]
+    fn test_block_period_arg() {
+        let key = Signer::random().sk.display_secret().to_string();
+        let parse = |period: &str| {
+            Args::try_parse_from(["sequencer", "--key", &key, "--block-period", period])
+        };
+        assert_eq!(parse("500").unwrap().block_period, 500);
+        assert!(parse("0").is_err());
+    }
 }